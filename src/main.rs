@@ -4,16 +4,19 @@ mod path_rendering;
 
 use {
     geometric_algebra::{
-        ppga3d::{Rotor, Translator},
+        ppga3d::{Point, Rotor, Translator},
         GeometricProduct, One,
     },
     macroquad::prelude::*,
-    miniquad::{window::screen_size, PassAction, TextureFormat, TextureParams},
+    miniquad::{window::screen_size, TextureFormat, TextureParams},
+    model::GradientType,
+    nanolottie::paint::Paint,
     path_rendering::{
-        raw_miniquad,
+        raw_miniquad::{self, CoverPaint},
+        renderer::{RenderOperation, Shape},
         utils::{matrix_multiplication, motor3d_to_mat4, perspective_projection},
-        vertex::{Vertex0, Vertex2f, Vertex3f},
     },
+    std::collections::HashMap,
 };
 
 fn window_conf() -> Conf {
@@ -32,32 +35,32 @@ fn window_conf() -> Conf {
     }
 }
 
-// 6x subpixel AA pattern
-//
-//   R = (f(x - 2/3, y) + f(x - 1/3, y) + f(x, y)) / 3
-//   G = (f(x - 1/3, y) + f(x, y) + f(x + 1/3, y)) / 3
-//   B = (f(x, y) + f(x + 1/3, y) + f(x + 2/3, y)) / 3
-//
-// The shader would require three texture lookups if the texture format
-// stored data for offsets -1/3, 0, and +1/3 since the shader also needs
-// data for offsets -2/3 and +2/3. To avoid this, the texture format stores
-// data for offsets 0, +1/3, and +2/3 instead. That way the shader can get
-// data for offsets -2/3 and -1/3 with only one additional texture lookup.
-//
-const JITTER_PATTERN: [[f32; 2]; 6] = [
-    [-1. / 12.0, -5. / 12.0],
-    [1. / 12.0, 1. / 12.0],
-    [3. / 12.0, -1. / 12.0],
-    [5. / 12.0, 5. / 12.0],
-    [7. / 12.0, -3. / 12.0],
-    [9. / 12.0, 3. / 12.0],
-];
-
 #[macroquad::main(window_conf)]
 async fn main() {
-    // let model = nanolottie::load_lottie_file(false);
+    let model = nanolottie::load_lottie_file(false);
     // dbg!(&model);
 
+    // Images are decoded once, before the raw miniquad context is borrowed
+    let mut textures: HashMap<String, Texture2D> = HashMap::new();
+    for (id, data) in nanolottie::load_images(&model) {
+        let image = data.and_then(|data| {
+            Image::from_file_with_format(&data, None)
+                .map_err(|_| nanolottie::Error::InvalidImage(id.clone()))
+        });
+        match image {
+            Ok(image) => {
+                textures.insert(id, Texture2D::from_image(&image));
+            }
+            // The layers of images which can not be read or decoded are skipped
+            Err(error) => eprintln!("Skipping image: {error:?}"),
+        }
+    }
+
+    // Errors like parent cycles or missing parents are a property of the file, so they are reported once
+    if let Err(error) = nanolottie::render_lottie(&model, model.start_frame) {
+        eprintln!("Invalid animation: {error:?}");
+    }
+
     let mut stage = {
         let InternalGlContext {
             quad_context: ctx, ..
@@ -68,7 +71,7 @@ async fn main() {
 
     let mut offscreen_width = 0;
     let mut offscreen_height = 0;
-    let mut offscreen_pass = {
+    let (mut offscreen_pass, mut offscreen_stencil_buffer) = {
         let InternalGlContext {
             quad_context: ctx, ..
         } = unsafe { get_internal_gl() };
@@ -80,9 +83,12 @@ async fn main() {
             ..Default::default()
         });
         stage.color_cover_bindings.images[0] = color_img;
-        ctx.new_render_pass(color_img, None)
+        // Even-odd fills count the parity of the samples in the stencil buffer
+        raw_miniquad::new_stencil_render_pass(ctx, color_img, offscreen_width, offscreen_height)
     };
 
+    let mut shape_bindings_pool: Vec<raw_miniquad::ShapeBindings> = vec![];
+
     loop {
         clear_background(DARKGRAY);
 
@@ -90,7 +96,7 @@ async fn main() {
             offscreen_width = screen_size().0 as u32;
             offscreen_height = screen_size().1 as u32;
 
-            offscreen_pass = {
+            (offscreen_pass, offscreen_stencil_buffer) = {
                 let InternalGlContext {
                     quad_context: ctx, ..
                 } = unsafe { get_internal_gl() };
@@ -102,30 +108,30 @@ async fn main() {
                 });
                 stage.color_cover_bindings.images[0] = color_img;
 
-                let new_offscreen_pass = ctx.new_render_pass(color_img, None);
+                let new_offscreen_pass = raw_miniquad::new_stencil_render_pass(
+                    ctx,
+                    color_img,
+                    offscreen_width,
+                    offscreen_height,
+                );
 
                 ctx.delete_render_pass(offscreen_pass);
+                raw_miniquad::delete_stencil_buffer(offscreen_stencil_buffer);
                 new_offscreen_pass
             };
         }
 
-        // draw_lottie(&model);
+        let frame = model.start_frame
+            + (get_time() as f32 * model.frame_rate) % (model.end_frame - model.start_frame);
+        // Errors were reported after loading, such frames stay empty
+        let commands = nanolottie::render_lottie(&model, frame).unwrap_or_default();
 
         {
-            let draw_to_texture = true;
             let mut gl = unsafe { get_internal_gl() };
 
             // Ensure that macroquad's shapes are not going to be lost
             gl.flush();
 
-            if draw_to_texture {
-                gl.quad_context.begin_pass(
-                    Some(offscreen_pass),
-                    PassAction::clear_color(0.0, 0.0, 0.0, 0.0),
-                );
-            } else {
-                gl.quad_context.begin_default_pass(PassAction::Nothing);
-            }
             let projection_matrix = matrix_multiplication(
                 &perspective_projection(
                     std::f32::consts::PI * 0.5,
@@ -138,116 +144,128 @@ async fn main() {
                 ),
             );
 
-            // let projection_matrix = motor3d_to_mat4(
-            //     &Translator::new(1.0, 0.0, 0.0, 0.0).geometric_product(Rotor::one()),
-            // );
-
-            for j in 0..JITTER_PATTERN.len() {
-                let offset = JITTER_PATTERN[j];
-                for (pipeline, bindings, begin_offset, end_offset, vertex_size) in [
-                    (
-                        &stage.fill_solid_pipeline,
-                        &stage.fill_solid_bindings,
-                        0,
-                        stage.shape2.vertex_offsets[0],
-                        std::mem::size_of::<Vertex0>(),
-                    ),
-                    (
-                        &stage.fill_integral_quadratic_curve_pipeline,
-                        &stage.fill_integral_quadratic_curve_bindings,
-                        stage.shape2.vertex_offsets[0],
-                        stage.shape2.vertex_offsets[1],
-                        std::mem::size_of::<Vertex2f>(),
-                    ),
-                    (
-                        &stage.fill_rational_quadratic_curve_pipeline,
-                        &stage.fill_rational_quadratic_curve_bindings,
-                        stage.shape2.vertex_offsets[2],
-                        stage.shape2.vertex_offsets[3],
-                        std::mem::size_of::<Vertex3f>(),
-                    ),
-                ] {
-                    let scale = 1. / screen_dpi_scale();
-                    // dbg!(offset);
-                    gl.quad_context.apply_pipeline(pipeline);
-                    gl.quad_context.apply_bindings(bindings);
-                    let mut in_color = [1.0; 4];
+            // Fit the composition into the view and flip it, as Lottie uses y-down coordinates
+            let aspect_ratio = screen_size().0 / screen_size().1;
+            let scale = (2.0 * aspect_ratio / model.width as f32).min(2.0 / model.height as f32);
+            let model_matrix = [
+                Point::new(scale, 0.0, 0.0, 0.0),
+                Point::new(0.0, -scale, 0.0, 0.0),
+                Point::new(0.0, 0.0, 1.0, 0.0),
+                Point::new(
+                    -0.5 * model.width as f32 * scale,
+                    0.5 * model.height as f32 * scale,
+                    0.0,
+                    1.0,
+                ),
+            ];
 
-                    if j % 2 == 0 {
-                        in_color[0] = if j == 0 { 1.0 } else { 0.0 };
-                        in_color[1] = if j == 2 { 1.0 } else { 0.0 };
-                        in_color[2] = if j == 4 { 1.0 } else { 0.0 };
+            let mut clipped = false;
+            let mut used_shape_bindings = 0;
+            for command in &commands {
+                match command.operation {
+                    RenderOperation::UnClip => {
+                        stage.unclip(gl.quad_context);
+                        clipped = false;
+                        continue;
+                    }
+                    RenderOperation::SaveMatteContext | RenderOperation::SaveBlendContext => {
+                        stage.save_offscreen_context(gl.quad_context);
+                        continue;
+                    }
+                    RenderOperation::ApplyMatteContext | RenderOperation::ApplyBlendContext => {
+                        stage.apply_offscreen_context(gl.quad_context);
+                        continue;
                     }
-                    // let jitter_matrix = projection_matrix;
-                    let jitter_matrix = matrix_multiplication(
+                    RenderOperation::RestoreMatteContext { luma, inverted } => {
+                        stage.restore_matte_context(gl.quad_context, luma, inverted);
+                        continue;
+                    }
+                    RenderOperation::RestoreBlendContext(blending) => {
+                        stage.restore_blend_context(gl.quad_context, blending);
+                        continue;
+                    }
+                    RenderOperation::Clip => {
+                        clipped = true;
+                        // An empty clip area hides everything, clearing the stencil buffer is enough
+                        if command.paths.is_empty() {
+                            stage.unclip(gl.quad_context);
+                            continue;
+                        }
+                    }
+                    _ => {}
+                }
+                if command.paths.is_empty() {
+                    continue;
+                }
+                let paint = match &command.paint {
+                    Paint::Solid(color) => CoverPaint::Color(*color),
+                    Paint::Image(image) => match textures.get(&image.asset_id) {
+                        Some(texture) => CoverPaint::Image {
+                            texture: texture.raw_miniquad_id(),
+                            matrix: image.matrix,
+                            opacity: image.opacity,
+                        },
+                        None => continue,
+                    },
+                    Paint::Gradient(gradient) => CoverPaint::Gradient {
+                        radial: !matches!(gradient.gradient_type, GradientType::Linear),
+                        start: gradient.start,
+                        end: gradient.end,
+                        focal: gradient.highlight,
+                        stops: gradient
+                            .limited_stops(raw_miniquad::MAX_GRADIENT_STOPS)
+                            .iter()
+                            .map(|stop| (stop.offset, stop.color))
+                            .collect(),
+                    },
+                };
+                let shape = match Shape::from_paths(&command.dynamic_stroke_options, &command.paths)
+                {
+                    Ok(shape) => shape,
+                    Err(error) => {
+                        eprintln!("Skipping shape: {error:?}");
+                        // A clip area which can not be built hides everything like an empty one
+                        if command.operation == RenderOperation::Clip {
+                            stage.unclip(gl.quad_context);
+                        }
+                        continue;
+                    }
+                };
+                // The buffers of the previous frame are refilled in the same order
+                if used_shape_bindings < shape_bindings_pool.len() {
+                    shape_bindings_pool[used_shape_bindings].update(gl.quad_context, &shape);
+                } else {
+                    shape_bindings_pool
+                        .push(raw_miniquad::ShapeBindings::new(gl.quad_context, &shape));
+                }
+                let shape_bindings = &shape_bindings_pool[used_shape_bindings];
+                used_shape_bindings += 1;
+                if command.operation == RenderOperation::Clip {
+                    stage.clip_shape(
+                        gl.quad_context,
+                        offscreen_pass,
+                        shape_bindings,
                         &projection_matrix,
-                        &motor3d_to_mat4(
-                            &Translator::new(
-                                1.0,
-                                (offset[0] * scale) / screen_size().0,
-                                (offset[1] * scale) / screen_size().1,
-                                0.0,
-                            )
-                            .geometric_product(Rotor::one()),
-                        ),
+                        &model_matrix,
+                        command.fill_rule,
                     );
-                    gl.quad_context
-                        .apply_uniforms(miniquad::UniformsSource::table(
-                            &raw_miniquad::shader::Uniforms {
-                                transform_row_0: jitter_matrix[0].into(),
-                                transform_row_1: jitter_matrix[1].into(),
-                                transform_row_2: jitter_matrix[2].into(),
-                                transform_row_3: jitter_matrix[3].into(),
-                                in_color,
-                            },
-                        ));
-
-                    gl.quad_context.draw(
-                        0,
-                        ((end_offset - begin_offset) / vertex_size)
-                            .try_into()
-                            .unwrap(),
-                        1,
+                } else {
+                    stage.draw_shape(
+                        gl.quad_context,
+                        offscreen_pass,
+                        shape_bindings,
+                        &projection_matrix,
+                        &model_matrix,
+                        &paint,
+                        command.fill_rule,
+                        command.blending,
+                        clipped,
                     );
                 }
             }
-            gl.quad_context.end_render_pass();
-
-            if draw_to_texture {
-                gl.quad_context.begin_default_pass(PassAction::Nothing);
-
-                gl.quad_context.apply_pipeline(&stage.color_cover_pipeline);
-                gl.quad_context.apply_bindings(&stage.color_cover_bindings);
-
-                gl.quad_context
-                    .apply_uniforms(miniquad::UniformsSource::table(
-                        &raw_miniquad::shader::CoverUniforms {
-                            transform_row_0: projection_matrix[0].into(),
-                            transform_row_1: projection_matrix[1].into(),
-                            transform_row_2: projection_matrix[2].into(),
-                            transform_row_3: projection_matrix[3].into(),
-                            in_color: [0.0; 4],
-                            in_rect: [
-                                stage.shape2.convex_box[0].floor(),
-                                1. - stage.shape2.convex_box[3].ceil(),
-                                stage.shape2.convex_box[2].ceil(),
-                                1. - stage.shape2.convex_box[1].floor(),
-                            ],
-                        },
-                    ));
-
-                let begin_offset = stage.shape2.vertex_offsets[5];
-                let end_offset = stage.shape2.vertex_offsets[6];
-                let vertex_size = std::mem::size_of::<Vertex2f>();
-                gl.quad_context.draw(
-                    0,
-                    ((end_offset - begin_offset) / vertex_size)
-                        .try_into()
-                        .unwrap(),
-                    1,
-                );
-
-                gl.quad_context.end_render_pass();
+            // Frees the buffers of shapes which are gone
+            for shape_bindings in shape_bindings_pool.drain(used_shape_bindings..) {
+                shape_bindings.delete(gl.quad_context);
             }
         }
 
@@ -83,7 +83,8 @@ impl DeJson for Value {
                     _ => Err(s.err_token("U64 or {")),
                 }
             }
-            _ => Err(s.err_token("U64 or [")),
+            DeJsonTok::CurlyOpen => Ok(Self::TextDocument(DeJson::de_json(s, i)?)),
+            _ => Err(s.err_token("U64, [ or {")),
         }
     }
 }
@@ -97,6 +98,9 @@ impl SerJson for Value {
             Self::List(f0) => {
                 f0.ser_json(d, s);
             }
+            Self::TextDocument(f0) => {
+                f0.ser_json(d, s);
+            }
             _ => todo!(),
         }
     }
@@ -123,15 +127,17 @@ pub struct TextDocument {
         serialize_with = "array_from_rgba",
         default
     )]
-    stroke_color: Rgba,
+    pub stroke_color: Rgba,
     #[nserde(rename = "sw", default)]
-    stroke_width: f32,
+    pub stroke_width: f32,
     #[nserde(rename = "of", default)]
-    stroke_above_fill: bool,
+    pub stroke_above_fill: bool,
     #[nserde(rename = "lh", default)]
-    line_height: Option<f32>,
+    pub line_height: Option<f32>,
     #[nserde(rename = "j", default)]
     pub justify: TextJustify,
+    #[nserde(rename = "tr", default)]
+    pub tracking: f32,
     #[nserde(rename = "ls", default)]
     pub baseline_shift: f32,
     // TODO:
@@ -139,8 +145,8 @@ pub struct TextDocument {
     sz: Vec<f32>,
     #[nserde(default)]
     ps: Vec<f32>,
-    #[nserde(default)]
-    ca: TextCaps,
+    #[nserde(rename = "ca", default)]
+    pub caps: TextCaps,
 }
 
 impl Default for TextDocument {
@@ -156,11 +162,26 @@ impl Default for TextDocument {
             baseline_shift: 0.0,
             value: String::new(),
             justify: TextJustify::Left,
+            tracking: 0.0,
             sz: vec![],
             ps: vec![],
-            ca: TextCaps::Regular,
+            caps: TextCaps::Regular,
+        }
+    }
+}
+
+impl FromTo<Value> for TextDocument {
+    fn from(v: Value) -> Self {
+        match v {
+            Value::TextDocument(document) => document,
+            // Keyframes of text documents only ever hold documents, anything else renders no text
+            _ => TextDocument::default(),
         }
     }
+
+    fn to(self) -> Value {
+        Value::TextDocument(self)
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -330,7 +351,7 @@ mod vector_2_d {
     }
 }
 
-type Vector2D = vector_2_d::Vector2D<f32>;
+pub type Vector2D = vector_2_d::Vector2D<f32>;
 
 impl FromTo<Value> for Vector2D {
     fn from(v: Value) -> Self {
@@ -389,8 +410,9 @@ impl DeJson for Asset {
                         match AsRef::<str>::as_ref(&s.strbuf) {
                             "e" => {
                                 s.next_colon(i)?;
-                                match _asset.as_mut() {
-                                    Some(Asset::Media(media)) => {
+                                match _asset.get_or_insert_with(|| Asset::Media(Default::default()))
+                                {
+                                    Asset::Media(media) => {
                                         if let Some(match_name) = _match_name {
                                             media.match_name = match_name;
                                             _match_name = None;
@@ -449,8 +471,9 @@ impl DeJson for Asset {
                             }
                             "h" => {
                                 s.next_colon(i)?;
-                                match _asset.as_mut() {
-                                    Some(Asset::Media(media)) => {
+                                match _asset.get_or_insert_with(|| Asset::Media(Default::default()))
+                                {
+                                    Asset::Media(media) => {
                                         if let Some(name) = _name {
                                             media.name = name;
                                             _name = None;
@@ -465,7 +488,7 @@ impl DeJson for Asset {
                                         }
                                         media.height = DeJson::de_json(s, i)?;
                                     }
-                                    Some(Asset::Precomposition(precomposition)) => {
+                                    Asset::Precomposition(precomposition) => {
                                         if let Some(name) = _name {
                                             precomposition.name = name;
                                             _name = None;
@@ -480,7 +503,6 @@ impl DeJson for Asset {
                                         }
                                         precomposition.height = DeJson::de_json(s, i)?;
                                     }
-                                    _ => de_unreachable(s),
                                 }
                             }
                             "id" => {
@@ -500,6 +522,23 @@ impl DeJson for Asset {
                             }
                             "layers" => {
                                 s.next_colon(i)?;
+                                // Width and height may precede the layers which make the asset a precomposition
+                                match _asset.take() {
+                                    None => {
+                                        _asset = Some(Asset::Precomposition(Default::default()))
+                                    }
+                                    Some(Asset::Media(media)) if media.filename.is_empty() => {
+                                        _asset = Some(Asset::Precomposition(Precomposition {
+                                            id: media.id,
+                                            name: media.name,
+                                            match_name: media.match_name,
+                                            width: media.width,
+                                            height: media.height,
+                                            ..Default::default()
+                                        }))
+                                    }
+                                    asset => _asset = asset,
+                                }
                                 match _asset.as_mut() {
                                     Some(Asset::Precomposition(precomposition)) => {
                                         if let Some(name) = _name {
@@ -551,8 +590,9 @@ impl DeJson for Asset {
                             }
                             "p" => {
                                 s.next_colon(i)?;
-                                match _asset.as_mut() {
-                                    Some(Asset::Media(media)) => {
+                                match _asset.get_or_insert_with(|| Asset::Media(Default::default()))
+                                {
+                                    Asset::Media(media) => {
                                         if let Some(name) = _name {
                                             media.name = name;
                                             _name = None;
@@ -572,8 +612,9 @@ impl DeJson for Asset {
                             }
                             "u" => {
                                 s.next_colon(i)?;
-                                match _asset.as_mut() {
-                                    Some(Asset::Media(media)) => {
+                                match _asset.get_or_insert_with(|| Asset::Media(Default::default()))
+                                {
+                                    Asset::Media(media) => {
                                         if let Some(name) = _name {
                                             media.name = name;
                                             _name = None;
@@ -593,8 +634,9 @@ impl DeJson for Asset {
                             }
                             "w" => {
                                 s.next_colon(i)?;
-                                match _asset.as_mut() {
-                                    Some(Asset::Media(media)) => {
+                                match _asset.get_or_insert_with(|| Asset::Media(Default::default()))
+                                {
+                                    Asset::Media(media) => {
                                         if let Some(name) = _name {
                                             media.name = name;
                                             _name = None;
@@ -609,7 +651,7 @@ impl DeJson for Asset {
                                         }
                                         media.width = DeJson::de_json(s, i)?;
                                     }
-                                    Some(Asset::Precomposition(precomposition)) => {
+                                    Asset::Precomposition(precomposition) => {
                                         if let Some(name) = _name {
                                             precomposition.name = name;
                                             _name = None;
@@ -624,7 +666,6 @@ impl DeJson for Asset {
                                         }
                                         precomposition.width = DeJson::de_json(s, i)?;
                                     }
-                                    _ => de_unreachable(s),
                                 }
                             }
                             _ => de_unreachable(s),
@@ -774,6 +815,10 @@ pub struct Layer {
     pub content: LayerContent,
     pub time_stretch: Option<f32>,
     pub matte_mode: Option<MatteMode>,
+    /// Only used as track matte of another layer, not rendered by itself
+    pub is_matte: bool,
+    /// Index of the track matte layer, otherwise it is the layer above
+    pub matte_parent: Option<u32>,
     pub blend_mode: Option<BlendMode>,
     pub has_mask: bool,
     pub masks_properties: Vec<Mask>,
@@ -907,14 +952,14 @@ pub struct Mask {
     #[nserde(rename = "nm", default)]
     pub name: String,
     #[nserde(rename = "inv", default)]
-    inverted: bool,
+    pub inverted: bool,
     #[nserde(rename = "pt")]
     pub points: Animated<Vec<Bezier>>,
     #[nserde(rename = "o")]
     pub opacity: Animated<f32>,
     pub mode: MaskMode,
     #[nserde(rename = "e", default)]
-    expand: Option<Animated<f32>>,
+    pub expand: Option<Animated<f32>>,
 }
 
 #[derive(SerJson, DeJson, Debug, Clone, Copy)]
@@ -948,6 +993,8 @@ impl DeJson for Layer {
             let mut _end_frame = None;
             let mut _time_stretch = None;
             let mut _matte_mode = None;
+            let mut _is_matte = None;
+            let mut _matte_parent = None;
             let mut _blend_mode = None;
             let mut _has_mask = None;
             let mut _masks_properties = None;
@@ -1063,7 +1110,8 @@ impl DeJson for Layer {
                             Some(LayerContent::PreCompositionRef(PreCompositionRef {
                                 ref mut ref_id,
                                 ..
-                            })) => {
+                            }))
+                            | Some(LayerContent::MediaRef(MediaRef { ref mut ref_id })) => {
                                 *ref_id = DeJson::de_json(s, i)?;
                             }
                             _ => de_unreachable(s),
@@ -1071,19 +1119,22 @@ impl DeJson for Layer {
                     }
                     "sc" => {
                         s.next_colon(i)?;
-                        assert!(matches!(
-                            _content,
-                            None | Some(LayerContent::SolidColor { .. })
-                        ));
-                        // _content = Some(DeJson::de_json(s, i)?);
+                        let hex: String = DeJson::de_json(s, i)?;
+                        let Some(value) = Rgba::from_hex(&hex) else {
+                            return Err(s.err_parse("sc"));
+                        };
+                        match _content.get_or_insert_with(LayerContent::default_solid_color) {
+                            LayerContent::SolidColor { ref mut color, .. } => *color = value,
+                            _ => de_unreachable(s),
+                        }
                     }
                     "sh" => {
                         s.next_colon(i)?;
-                        assert!(matches!(
-                            _content,
-                            None | Some(LayerContent::SolidColor { .. })
-                        ));
-                        // _content = Some(DeJson::de_json(s, i)?);
+                        let value = DeJson::de_json(s, i)?;
+                        match _content.get_or_insert_with(LayerContent::default_solid_color) {
+                            LayerContent::SolidColor { ref mut height, .. } => *height = value,
+                            _ => de_unreachable(s),
+                        }
                     }
                     "shapes" => {
                         s.next_colon(i)?;
@@ -1110,11 +1161,11 @@ impl DeJson for Layer {
                     }
                     "sw" => {
                         s.next_colon(i)?;
-                        assert!(matches!(
-                            _content,
-                            None | Some(LayerContent::SolidColor { .. })
-                        ));
-                        // _content = Some(DeJson::de_json(s, i)?);
+                        let value = DeJson::de_json(s, i)?;
+                        match _content.get_or_insert_with(LayerContent::default_solid_color) {
+                            LayerContent::SolidColor { ref mut width, .. } => *width = value,
+                            _ => de_unreachable(s),
+                        }
                     }
                     "tm" => {
                         s.next_colon(i)?;
@@ -1133,6 +1184,23 @@ impl DeJson for Layer {
                             _ => unreachable!(),
                         }
                     }
+                    "t" => {
+                        s.next_colon(i)?;
+                        let text = DeJson::de_json(s, i)?;
+                        match _content.as_mut() {
+                            None => _content = Some(LayerContent::Text(text)),
+                            Some(LayerContent::Text(ref mut value)) => *value = text,
+                            _ => de_unreachable(s),
+                        }
+                    }
+                    "td" => {
+                        s.next_colon(i)?;
+                        _is_matte = Some(DeJson::de_json(s, i)?);
+                    }
+                    "tp" => {
+                        s.next_colon(i)?;
+                        _matte_parent = Some(DeJson::de_json(s, i)?);
+                    }
                     "tt" => {
                         s.next_colon(i)?;
                         _matte_mode = Some(DeJson::de_json(s, i)?);
@@ -1145,16 +1213,11 @@ impl DeJson for Layer {
                                     _content =
                                         Some(LayerContent::PreCompositionRef(Default::default()))
                                 }
-                                1 => {
-                                    _content = Some(LayerContent::SolidColor {
-                                        color: Default::default(),
-                                        width: Default::default(),
-                                        height: Default::default(),
-                                    })
-                                }
+                                1 => _content = Some(LayerContent::default_solid_color()),
                                 2 => _content = Some(LayerContent::MediaRef(Default::default())),
                                 3 => _content = Some(LayerContent::Empty),
                                 4 => _content = Some(LayerContent::Shape(Default::default())),
+                                5 => _content = Some(LayerContent::Text(Default::default())),
                                 6 => _content = Some(LayerContent::MediaRef(Default::default())),
                                 _ => de_unreachable(s),
                             },
@@ -1220,8 +1283,8 @@ impl DeJson for Layer {
                 },
                 id: Default::default(),
                 auto_orient: {
-                    if let Some(t) = _auto_orient {
-                        From::<&BoolFromInt>::from(&t)
+                    if let Some(t) = &_auto_orient {
+                        From::<&BoolFromInt>::from(t)
                     } else {
                         Default::default()
                     }
@@ -1254,6 +1317,20 @@ impl DeJson for Layer {
                         None
                     }
                 },
+                is_matte: {
+                    if let Some(t) = &_is_matte {
+                        From::<&BoolFromInt>::from(t)
+                    } else {
+                        Default::default()
+                    }
+                },
+                matte_parent: {
+                    if let Some(t) = _matte_parent {
+                        t
+                    } else {
+                        None
+                    }
+                },
                 blend_mode: {
                     if let Some(t) = _blend_mode {
                         t
@@ -1298,7 +1375,13 @@ impl DeJson for Layer {
                 },
                 transform: {
                     if let Some(t) = _transform {
-                        t
+                        // Auto-orient is a flag of the layer, but it is evaluated with the transform
+                        t.map(|mut transform: Transform| {
+                            transform.auto_orient = _auto_orient
+                                .as_ref()
+                                .map_or(false, From::<&BoolFromInt>::from);
+                            transform
+                        })
                     } else {
                         None
                     }
@@ -1430,6 +1513,34 @@ impl SerJson for Layer {
                 s.field(d + 1, "tm");
                 pre_composition_ref.time_remapping.ser_json(d + 1, s);
             }
+            LayerContent::Text(text) => {
+                i32::ser_json(&5, d + 1, s);
+                s.conl();
+                s.field(d + 1, "t");
+                text.ser_json(d + 1, s);
+            }
+            LayerContent::SolidColor {
+                color,
+                height,
+                width,
+            } => {
+                i32::ser_json(&1, d + 1, s);
+                s.conl();
+                s.field(d + 1, "sc");
+                color.to_hex().ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "sw");
+                width.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "sh");
+                height.ser_json(d + 1, s);
+            }
+            LayerContent::MediaRef(media_ref) => {
+                i32::ser_json(&2, d + 1, s);
+                s.conl();
+                s.field(d + 1, "refId");
+                media_ref.ref_id.ser_json(d + 1, s);
+            }
             _ => unreachable!(),
         }
         if first_field_was_serialized {
@@ -1446,6 +1557,26 @@ impl SerJson for Layer {
             s.conl();
         }
         first_field_was_serialized = true;
+        s.field(d + 1, "td");
+        {
+            let proxy: BoolFromInt = Into::into(&self.is_matte);
+            proxy
+        }
+        .ser_json(d + 1, s);
+        if first_field_was_serialized {
+            s.conl();
+        }
+        first_field_was_serialized = true;
+        s.field(d + 1, "tp");
+        if let Some(t) = &self.matte_parent {
+            t.ser_json(d + 1, s);
+        } else {
+            Option::<i32>::ser_json(&None, d + 1, s);
+        };
+        if first_field_was_serialized {
+            s.conl();
+        }
+        first_field_was_serialized = true;
         s.field(d + 1, "bm");
         if let Some(t) = &self.blend_mode {
             t.ser_json(d + 1, s);
@@ -1479,18 +1610,28 @@ pub enum LayerContent {
     MediaRef(MediaRef),
     Empty,
     Shape(ShapeGroup),
-    // Text(TextAnimationData),
+    Text(TextAnimationData),
     Media(Media),
 }
 
+impl LayerContent {
+    fn default_solid_color() -> Self {
+        Self::SolidColor {
+            color: Default::default(),
+            height: Default::default(),
+            width: Default::default(),
+        }
+    }
+}
+
 #[derive(SerJson, DeJson, Debug, Clone, Default)]
 pub struct PreCompositionRef {
     #[nserde(rename = "refId")]
     pub ref_id: String,
     #[nserde(rename = "w")]
-    width: u32,
+    pub width: u32,
     #[nserde(rename = "h")]
-    height: u32,
+    pub height: u32,
     #[nserde(rename = "tm")]
     pub time_remapping: Option<Animated<f32>>,
 }
@@ -1516,6 +1657,30 @@ impl Rgba {
     pub fn new_u8(r: u8, g: u8, b: u8, a: u8) -> Rgba {
         Rgba { r, g, b, a }
     }
+
+    /// Parses colors like `#rrggbb` or `#rrggbbaa`
+    pub fn from_hex(hex: &str) -> Option<Rgba> {
+        let digits = hex.strip_prefix('#')?;
+        if !matches!(digits.len(), 6 | 8) || !digits.is_ascii() {
+            return None;
+        }
+        let channel =
+            |index: usize| u8::from_str_radix(digits.get(index * 2..index * 2 + 2)?, 16).ok();
+        Some(Rgba {
+            r: channel(0)?,
+            g: channel(1)?,
+            b: channel(2)?,
+            a: if digits.len() == 8 { channel(3)? } else { 255 },
+        })
+    }
+
+    pub fn to_hex(&self) -> String {
+        let hex = format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b);
+        match self.a {
+            255 => hex,
+            a => format!("{hex}{a:02x}"),
+        }
+    }
 }
 
 impl Default for Rgba {
@@ -1600,6 +1765,121 @@ impl SerJson for ShapeLayer {
                 s.field(d + 1, "s");
                 ellipse.size.ser_json(d + 1, s);
             }
+            Shape::PolyStar(star) => {
+                String::ser_json(&"sr".into(), d + 1, s);
+                s.conl();
+                s.field(d + 1, "d");
+                star.direction.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "p");
+                star.position.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "or");
+                star.outer_radius.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "os");
+                star.outer_roundness.ser_json(d + 1, s);
+                if let Some(inner_radius) = &star.inner_radius {
+                    s.conl();
+                    s.field(d + 1, "ir");
+                    inner_radius.ser_json(d + 1, s);
+                }
+                if let Some(inner_roundness) = &star.inner_roundness {
+                    s.conl();
+                    s.field(d + 1, "is");
+                    inner_roundness.ser_json(d + 1, s);
+                }
+                s.conl();
+                s.field(d + 1, "r");
+                star.rotation.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "pt");
+                star.points.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "sy");
+                star.star_type.ser_json(d + 1, s);
+            }
+            Shape::Trim(trim) => {
+                String::ser_json(&"tm".into(), d + 1, s);
+                s.conl();
+                s.field(d + 1, "s");
+                trim.start.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "e");
+                trim.end.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "o");
+                trim.offset.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "m");
+                trim.multiple_shape.ser_json(d + 1, s);
+            }
+            Shape::Repeater(repeater) => {
+                String::ser_json(&"rp".into(), d + 1, s);
+                s.conl();
+                s.field(d + 1, "c");
+                repeater.copies.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "o");
+                repeater.offset.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "m");
+                repeater.composite.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "tr");
+                repeater.transform.ser_json(d + 1, s);
+            }
+            Shape::RoundedCorners { radius } => {
+                String::ser_json(&"rd".into(), d + 1, s);
+                s.conl();
+                s.field(d + 1, "r");
+                radius.ser_json(d + 1, s);
+            }
+            Shape::PuckerBloat { amount } => {
+                String::ser_json(&"pb".into(), d + 1, s);
+                s.conl();
+                s.field(d + 1, "a");
+                amount.ser_json(d + 1, s);
+            }
+            Shape::Twist { angle, center } => {
+                String::ser_json(&"tw".into(), d + 1, s);
+                s.conl();
+                s.field(d + 1, "a");
+                angle.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "c");
+                center.ser_json(d + 1, s);
+            }
+            Shape::Merge(merge) => {
+                String::ser_json(&"mm".into(), d + 1, s);
+                s.conl();
+                s.field(d + 1, "mm");
+                merge.mode.ser_json(d + 1, s);
+            }
+            Shape::OffsetPath(offset_path) => {
+                String::ser_json(&"op".into(), d + 1, s);
+                s.conl();
+                s.field(d + 1, "a");
+                offset_path.amount.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "lj");
+                offset_path.line_join.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "ml");
+                offset_path.miter_limit.ser_json(d + 1, s);
+            }
+            Shape::ZigZag(zig_zag) => {
+                String::ser_json(&"zz".into(), d + 1, s);
+                s.conl();
+                s.field(d + 1, "r");
+                zig_zag.frequency.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "s");
+                zig_zag.amplitude.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "pt");
+                zig_zag.point_type.ser_json(d + 1, s);
+            }
             Shape::Group { shapes } => {
                 String::ser_json(&"gr".into(), d + 1, s);
                 s.conl();
@@ -1694,6 +1974,46 @@ impl SerJson for ShapeLayer {
                 let colors: ColorListHelper = Into::into(&gradient_fill.gradient.colors);
                 colors.ser_json(d + 1, s);
             }
+            Shape::GradientStroke(gradient_stroke) => {
+                String::ser_json(&"gs".into(), d + 1, s);
+                s.conl();
+                s.field(d + 1, "lc");
+                gradient_stroke.line_cap.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "lj");
+                gradient_stroke.line_join.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "ml");
+                gradient_stroke.miter_limit.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "o");
+                gradient_stroke.opacity.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "w");
+                gradient_stroke.width.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "d");
+                gradient_stroke.dashes.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "s");
+                gradient_stroke.gradient.start.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "e");
+                gradient_stroke.gradient.end.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "t");
+                gradient_stroke.gradient.gradient_ty.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "h");
+                gradient_stroke.highlight_length.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "a");
+                gradient_stroke.highlight_angle.ser_json(d + 1, s);
+                s.conl();
+                s.field(d + 1, "g");
+                let colors: ColorListHelper = Into::into(&gradient_stroke.gradient.colors);
+                colors.ser_json(d + 1, s);
+            }
             _ => unreachable!(),
         }
         s.st_post(d);
@@ -1762,6 +2082,23 @@ impl DeJson for ShapeLayer {
                                     }
                                     _ => de_unreachable(s),
                                 },
+                                "sr" => match _shape.as_mut() {
+                                    None => {
+                                        let mut star = PolyStar::default();
+                                        if let Some(direction) = _direction {
+                                            star.direction = direction;
+                                            _direction = None;
+                                        }
+                                        _shape = Some(Shape::PolyStar(star));
+                                    }
+                                    Some(Shape::PolyStar(star)) => {
+                                        if let Some(direction) = _direction {
+                                            star.direction = direction;
+                                            _direction = None;
+                                        }
+                                    }
+                                    _ => de_unreachable(s),
+                                },
                                 "gr" => match _shape.as_ref() {
                                     None => {
                                         _shape = Some(Shape::Group {
@@ -1833,6 +2170,86 @@ impl DeJson for ShapeLayer {
                                     Some(Shape::GradientFill(_)) => (),
                                     _ => de_unreachable(s),
                                 },
+                                "gs" => match _shape.as_mut() {
+                                    None => {
+                                        let mut gradient_stroke = GradientStroke::default();
+                                        if let Some(dashes) = _dashes {
+                                            gradient_stroke.dashes = dashes;
+                                            _dashes = None;
+                                        }
+                                        _shape = Some(Shape::GradientStroke(gradient_stroke));
+                                    }
+                                    Some(Shape::GradientStroke(gradient_stroke)) => {
+                                        if let Some(dashes) = _dashes {
+                                            gradient_stroke.dashes = dashes;
+                                            _dashes = None;
+                                        }
+                                    }
+                                    _ => de_unreachable(s),
+                                },
+                                "tm" => match _shape.as_ref() {
+                                    None => {
+                                        _shape = Some(Shape::Trim(Default::default()));
+                                    }
+                                    Some(Shape::Trim(_)) => (),
+                                    _ => de_unreachable(s),
+                                },
+                                "mm" => match _shape.as_ref() {
+                                    None => {
+                                        _shape = Some(Shape::Merge(Default::default()));
+                                    }
+                                    Some(Shape::Merge(_)) => (),
+                                    _ => de_unreachable(s),
+                                },
+                                "op" => match _shape.as_ref() {
+                                    None => {
+                                        _shape = Some(Shape::OffsetPath(Default::default()));
+                                    }
+                                    Some(Shape::OffsetPath(_)) => (),
+                                    _ => de_unreachable(s),
+                                },
+                                "zz" => match _shape.as_ref() {
+                                    None => {
+                                        _shape = Some(Shape::ZigZag(Default::default()));
+                                    }
+                                    Some(Shape::ZigZag(_)) => (),
+                                    _ => de_unreachable(s),
+                                },
+                                "rd" => match _shape.as_ref() {
+                                    None => {
+                                        _shape = Some(Shape::RoundedCorners {
+                                            radius: Default::default(),
+                                        });
+                                    }
+                                    Some(Shape::RoundedCorners { .. }) => (),
+                                    _ => de_unreachable(s),
+                                },
+                                "pb" => match _shape.as_ref() {
+                                    None => {
+                                        _shape = Some(Shape::PuckerBloat {
+                                            amount: Default::default(),
+                                        });
+                                    }
+                                    Some(Shape::PuckerBloat { .. }) => (),
+                                    _ => de_unreachable(s),
+                                },
+                                "tw" => match _shape.as_ref() {
+                                    None => {
+                                        _shape = Some(Shape::Twist {
+                                            angle: Default::default(),
+                                            center: Default::default(),
+                                        });
+                                    }
+                                    Some(Shape::Twist { .. }) => (),
+                                    _ => de_unreachable(s),
+                                },
+                                "rp" => match _shape.as_ref() {
+                                    None => {
+                                        _shape = Some(Shape::Repeater(Default::default()));
+                                    }
+                                    Some(Shape::Repeater(_)) => (),
+                                    _ => de_unreachable(s),
+                                },
                                 _ => de_unreachable(s),
                             }
                             s.next_tok(i)?;
@@ -1862,22 +2279,39 @@ impl DeJson for ShapeLayer {
                             Some(Shape::GradientFill(gradient_fill)) => {
                                 gradient_fill.highlight_angle = DeJson::de_json(s, i)?;
                             }
+                            Some(Shape::OffsetPath(offset_path)) => {
+                                offset_path.amount = DeJson::de_json(s, i)?;
+                            }
+                            Some(Shape::PuckerBloat { amount }) => {
+                                *amount = DeJson::de_json(s, i)?;
+                            }
+                            Some(Shape::Twist { angle, .. }) => {
+                                *angle = DeJson::de_json(s, i)?;
+                            }
+                            Some(Shape::GradientStroke(gradient_stroke)) => {
+                                gradient_stroke.highlight_angle = DeJson::de_json(s, i)?;
+                            }
                             _ => de_unreachable(s),
                         }
                     }
                     "c" => {
                         s.next_colon(i)?;
-                        let color = DeJson::de_json(s, i)?;
                         match _shape.as_mut() {
                             Some(Shape::Fill(fill)) => {
-                                fill.color = color;
+                                fill.color = DeJson::de_json(s, i)?;
                             }
                             Some(Shape::Stroke(stroke)) => {
                                 if let Some(dashes) = _dashes {
                                     stroke.dashes = dashes;
                                     _dashes = None;
                                 }
-                                stroke.color = color;
+                                stroke.color = DeJson::de_json(s, i)?;
+                            }
+                            Some(Shape::Repeater(repeater)) => {
+                                repeater.copies = DeJson::de_json(s, i)?;
+                            }
+                            Some(Shape::Twist { center, .. }) => {
+                                *center = DeJson::de_json(s, i)?;
                             }
                             _ => de_unreachable(s),
                         }
@@ -1901,6 +2335,9 @@ impl DeJson for ShapeLayer {
                             Some(Shape::Ellipse(ellipse)) => {
                                 ellipse.direction = DeJson::de_json(s, i)?;
                             }
+                            Some(Shape::PolyStar(star)) => {
+                                star.direction = DeJson::de_json(s, i)?;
+                            }
                             Some(Shape::Path {
                                 ref mut direction, ..
                             }) => {
@@ -1913,6 +2350,9 @@ impl DeJson for ShapeLayer {
                                 }
                                 stroke.dashes = DeJson::de_json(s, i)?;
                             }
+                            Some(Shape::GradientStroke(gradient_stroke)) => {
+                                gradient_stroke.dashes = DeJson::de_json(s, i)?;
+                            }
                             _ => de_unreachable(s),
                         }
                     }
@@ -1922,6 +2362,12 @@ impl DeJson for ShapeLayer {
                             Some(Shape::GradientFill(gradient_fill)) => {
                                 gradient_fill.gradient.end = DeJson::de_json(s, i)?;
                             }
+                            Some(Shape::Trim(trim)) => {
+                                trim.end = DeJson::de_json(s, i)?;
+                            }
+                            Some(Shape::GradientStroke(gradient_stroke)) => {
+                                gradient_stroke.gradient.end = DeJson::de_json(s, i)?;
+                            }
                             _ => de_unreachable(s),
                         }
                     }
@@ -1933,6 +2379,11 @@ impl DeJson for ShapeLayer {
                                 gradient_fill.gradient.colors =
                                     From::<&ColorListHelper>::from(&colors);
                             }
+                            Some(Shape::GradientStroke(gradient_stroke)) => {
+                                let colors = DeJson::de_json(s, i)?;
+                                gradient_stroke.gradient.colors =
+                                    From::<&ColorListHelper>::from(&colors);
+                            }
                             _ => de_unreachable(s),
                         }
                     }
@@ -1942,6 +2393,27 @@ impl DeJson for ShapeLayer {
                             Some(Shape::GradientFill(gradient_fill)) => {
                                 gradient_fill.highlight_length = DeJson::de_json(s, i)?;
                             }
+                            Some(Shape::GradientStroke(gradient_stroke)) => {
+                                gradient_stroke.highlight_length = DeJson::de_json(s, i)?;
+                            }
+                            _ => de_unreachable(s),
+                        }
+                    }
+                    "ir" => {
+                        s.next_colon(i)?;
+                        match _shape.as_mut() {
+                            Some(Shape::PolyStar(star)) => {
+                                star.inner_radius = Some(DeJson::de_json(s, i)?);
+                            }
+                            _ => de_unreachable(s),
+                        }
+                    }
+                    "is" => {
+                        s.next_colon(i)?;
+                        match _shape.as_mut() {
+                            Some(Shape::PolyStar(star)) => {
+                                star.inner_roundness = Some(DeJson::de_json(s, i)?);
+                            }
                             _ => de_unreachable(s),
                         }
                     }
@@ -1972,6 +2444,13 @@ impl DeJson for ShapeLayer {
                                 }
                                 stroke.line_cap = DeJson::de_json(s, i)?;
                             }
+                            Some(Shape::GradientStroke(gradient_stroke)) => {
+                                if let Some(dashes) = _dashes {
+                                    gradient_stroke.dashes = dashes;
+                                    _dashes = None;
+                                }
+                                gradient_stroke.line_cap = DeJson::de_json(s, i)?;
+                            }
                             _ => de_unreachable(s),
                         }
                     }
@@ -1985,6 +2464,28 @@ impl DeJson for ShapeLayer {
                                 }
                                 stroke.line_join = DeJson::de_json(s, i)?;
                             }
+                            Some(Shape::OffsetPath(offset_path)) => {
+                                offset_path.line_join = DeJson::de_json(s, i)?;
+                            }
+                            Some(Shape::GradientStroke(gradient_stroke)) => {
+                                if let Some(dashes) = _dashes {
+                                    gradient_stroke.dashes = dashes;
+                                    _dashes = None;
+                                }
+                                gradient_stroke.line_join = DeJson::de_json(s, i)?;
+                            }
+                            _ => de_unreachable(s),
+                        }
+                    }
+                    "m" => {
+                        s.next_colon(i)?;
+                        match _shape.as_mut() {
+                            Some(Shape::Trim(trim)) => {
+                                trim.multiple_shape = DeJson::de_json(s, i)?;
+                            }
+                            Some(Shape::Repeater(repeater)) => {
+                                repeater.composite = DeJson::de_json(s, i)?;
+                            }
                             _ => de_unreachable(s),
                         }
                     }
@@ -1998,6 +2499,25 @@ impl DeJson for ShapeLayer {
                                 }
                                 stroke.miter_limit = DeJson::de_json(s, i)?;
                             }
+                            Some(Shape::OffsetPath(offset_path)) => {
+                                offset_path.miter_limit = DeJson::de_json(s, i)?;
+                            }
+                            Some(Shape::GradientStroke(gradient_stroke)) => {
+                                if let Some(dashes) = _dashes {
+                                    gradient_stroke.dashes = dashes;
+                                    _dashes = None;
+                                }
+                                gradient_stroke.miter_limit = DeJson::de_json(s, i)?;
+                            }
+                            _ => de_unreachable(s),
+                        }
+                    }
+                    "mm" => {
+                        s.next_colon(i)?;
+                        match _shape.as_mut() {
+                            Some(Shape::Merge(merge)) => {
+                                merge.mode = DeJson::de_json(s, i)?;
+                            }
                             _ => de_unreachable(s),
                         }
                     }
@@ -2020,6 +2540,37 @@ impl DeJson for ShapeLayer {
                                 }
                                 stroke.opacity = DeJson::de_json(s, i)?;
                             }
+                            Some(Shape::Trim(trim)) => {
+                                trim.offset = DeJson::de_json(s, i)?;
+                            }
+                            Some(Shape::Repeater(repeater)) => {
+                                repeater.offset = DeJson::de_json(s, i)?;
+                            }
+                            Some(Shape::GradientStroke(gradient_stroke)) => {
+                                if let Some(dashes) = _dashes {
+                                    gradient_stroke.dashes = dashes;
+                                    _dashes = None;
+                                }
+                                gradient_stroke.opacity = DeJson::de_json(s, i)?;
+                            }
+                            _ => de_unreachable(s),
+                        }
+                    }
+                    "or" => {
+                        s.next_colon(i)?;
+                        match _shape.as_mut() {
+                            Some(Shape::PolyStar(star)) => {
+                                star.outer_radius = DeJson::de_json(s, i)?;
+                            }
+                            _ => de_unreachable(s),
+                        }
+                    }
+                    "os" => {
+                        s.next_colon(i)?;
+                        match _shape.as_mut() {
+                            Some(Shape::PolyStar(star)) => {
+                                star.outer_roundness = DeJson::de_json(s, i)?;
+                            }
                             _ => de_unreachable(s),
                         }
                     }
@@ -2040,12 +2591,27 @@ impl DeJson for ShapeLayer {
                                 }
                                 ellipse.position = DeJson::de_json(s, i)?;
                             }
+                            Some(Shape::PolyStar(star)) => {
+                                star.position = DeJson::de_json(s, i)?;
+                            }
                             Some(Shape::Transform(transform)) => {
                                 transform.position = DeJson::de_json(s, i)?;
                             }
                             _ => de_unreachable(s),
                         }
                     }
+                    "pt" => {
+                        s.next_colon(i)?;
+                        match _shape.as_mut() {
+                            Some(Shape::PolyStar(star)) => {
+                                star.points = DeJson::de_json(s, i)?;
+                            }
+                            Some(Shape::ZigZag(zig_zag)) => {
+                                zig_zag.point_type = DeJson::de_json(s, i)?;
+                            }
+                            _ => de_unreachable(s),
+                        }
+                    }
                     "r" => {
                         s.next_colon(i)?;
                         match _shape.as_mut() {
@@ -2065,9 +2631,15 @@ impl DeJson for ShapeLayer {
                             Some(Shape::RoundedCorners { radius }) => {
                                 *radius = DeJson::de_json(s, i)?;
                             }
+                            Some(Shape::PolyStar(star)) => {
+                                star.rotation = DeJson::de_json(s, i)?;
+                            }
                             Some(Shape::GradientFill(gradient_fill)) => {
                                 gradient_fill.fill_rule = DeJson::de_json(s, i)?;
                             }
+                            Some(Shape::ZigZag(zig_zag)) => {
+                                zig_zag.frequency = DeJson::de_json(s, i)?;
+                            }
                             _ => de_unreachable(s),
                         }
                     }
@@ -2094,6 +2666,15 @@ impl DeJson for ShapeLayer {
                             Some(Shape::GradientFill(gradient_fill)) => {
                                 gradient_fill.gradient.start = DeJson::de_json(s, i)?;
                             }
+                            Some(Shape::Trim(trim)) => {
+                                trim.start = DeJson::de_json(s, i)?;
+                            }
+                            Some(Shape::ZigZag(zig_zag)) => {
+                                zig_zag.amplitude = DeJson::de_json(s, i)?;
+                            }
+                            Some(Shape::GradientStroke(gradient_stroke)) => {
+                                gradient_stroke.gradient.start = DeJson::de_json(s, i)?;
+                            }
                             _ => de_unreachable(s),
                         }
                     }
@@ -2115,12 +2696,33 @@ impl DeJson for ShapeLayer {
                             _ => de_unreachable(s),
                         }
                     }
+                    "sy" => {
+                        s.next_colon(i)?;
+                        match _shape.as_mut() {
+                            Some(Shape::PolyStar(star)) => {
+                                star.star_type = DeJson::de_json(s, i)?;
+                            }
+                            _ => de_unreachable(s),
+                        }
+                    }
                     "t" => {
                         s.next_colon(i)?;
                         match _shape.as_mut() {
                             Some(Shape::GradientFill(gradient_fill)) => {
                                 gradient_fill.gradient.gradient_ty = DeJson::de_json(s, i)?;
                             }
+                            Some(Shape::GradientStroke(gradient_stroke)) => {
+                                gradient_stroke.gradient.gradient_ty = DeJson::de_json(s, i)?;
+                            }
+                            _ => de_unreachable(s),
+                        }
+                    }
+                    "tr" => {
+                        s.next_colon(i)?;
+                        match _shape.as_mut() {
+                            Some(Shape::Repeater(repeater)) => {
+                                repeater.transform = DeJson::de_json(s, i)?;
+                            }
                             _ => de_unreachable(s),
                         }
                     }
@@ -2134,6 +2736,13 @@ impl DeJson for ShapeLayer {
                                 }
                                 stroke.width = DeJson::de_json(s, i)?;
                             }
+                            Some(Shape::GradientStroke(gradient_stroke)) => {
+                                if let Some(dashes) = _dashes {
+                                    gradient_stroke.dashes = dashes;
+                                    _dashes = None;
+                                }
+                                gradient_stroke.width = DeJson::de_json(s, i)?;
+                            }
                             _ => de_unreachable(s),
                         }
                     }
@@ -2176,8 +2785,7 @@ impl DeJson for ShapeLayer {
 pub enum Shape {
     Rectangle(Rectangle),
     Ellipse(Ellipse),
-    // #[nserde(rename = "sr")]
-    // PolyStar(PolyStar),
+    PolyStar(PolyStar),
     Path {
         data: Animated<Vec<Bezier>>,
         direction: ShapeDirection,
@@ -2186,26 +2794,14 @@ pub enum Shape {
     Fill(Fill),
     Stroke(Stroke),
     GradientFill(GradientFill),
-    // #[nserde(rename = "gs")]
-    // GradientStroke(GradientStroke),
+    GradientStroke(GradientStroke),
     Group {
         // TODO: add np property
         shapes: Vec<ShapeLayer>,
     },
     Transform(Transform),
-    // #[nserde(rename = "rp")]
-    // Repeater {
-    //     #[nserde(rename = "c")]
-    //     copies: Animated<f32>,
-    //     #[nserde(rename = "o")]
-    //     offset: Animated<f32>,
-    //     #[nserde(rename = "m")]
-    //     composite: Composite,
-    //     #[nserde(rename = "tr")]
-    //     transform: RepeaterTransform,
-    // },
-    // #[nserde(rename = "tm")]
-    // Trim(Trim),
+    Repeater(Repeater),
+    Trim(Trim),
     RoundedCorners {
         radius: Animated<f32>,
     },
@@ -2216,29 +2812,9 @@ pub enum Shape {
         angle: Animated<f32>,
         center: Animated<Vector2D>,
     },
-    // #[nserde(rename = "mm")]
-    // Merge {
-    //     #[nserde(rename = "mm")]
-    //     mode: MergeMode,
-    // },
-    // #[nserde(rename = "op")]
-    // OffsetPath {
-    //     #[nserde(rename = "a")]
-    //     amount: Animated<f32>,
-    //     #[nserde(rename = "lj")]
-    //     line_join: LineJoin,
-    //     #[nserde(rename = "ml")]
-    //     miter_limit: f32,
-    // },
-    // #[nserde(rename = "zz")]
-    // ZigZag {
-    //     #[nserde(rename = "r")]
-    //     radius: Animated<f32>,
-    //     #[nserde(rename = "s")]
-    //     distance: Animated<f32>,
-    //     #[nserde(rename = "pt")]
-    //     ridges: Animated<f32>,
-    // },
+    Merge(Merge),
+    OffsetPath(OffsetPath),
+    ZigZag(ZigZag),
 }
 
 impl Default for Shape {
@@ -2311,35 +2887,71 @@ pub struct TextRange {
 
 #[derive(DeJson, SerJson, Debug, Clone)]
 pub struct TextRangeSelector {
-    #[nserde(rename = "t", proxy = "BoolFromInt")]
+    #[nserde(rename = "t", proxy = "BoolFromInt", default)]
     expressible: bool,
-    #[nserde(rename = "xe")]
-    max_ease: Animated<f32>,
-    #[nserde(rename = "ne")]
-    min_ease: Animated<f32>,
-    #[nserde(rename = "a")]
-    max_amount: Animated<f32>,
-    #[nserde(rename = "b")]
-    based_on: TextBased,
-    #[nserde(rename = "rn", proxy = "BoolFromInt")]
+    #[nserde(rename = "xe", default)]
+    pub max_ease: Option<Animated<f32>>,
+    #[nserde(rename = "ne", default)]
+    pub min_ease: Option<Animated<f32>>,
+    #[nserde(rename = "a", default_with = "default_number_100")]
+    pub max_amount: Animated<f32>,
+    #[nserde(rename = "b", default)]
+    pub based_on: TextBased,
+    // TODO: shuffle the characters
+    #[nserde(rename = "rn", proxy = "BoolFromInt", default)]
     randomize: bool,
-    #[nserde(rename = "sh")]
-    shape: TextShape,
+    #[nserde(rename = "sh", default)]
+    pub shape: TextShape,
     #[nserde(rename = "o", default)]
-    offset: Option<Animated<f32>>,
-    #[nserde(rename = "r")]
-    pub range_units: TextBased,
+    pub offset: Option<Animated<f32>>,
+    #[nserde(rename = "r", default)]
+    pub range_units: TextRangeUnits,
     #[nserde(rename = "sm", default)]
-    selector_smoothness: Option<Animated<f32>>,
+    pub selector_smoothness: Option<Animated<f32>>,
     #[nserde(rename = "s", default)]
     pub start: Option<Animated<f32>>,
     #[nserde(rename = "e", default)]
     pub end: Option<Animated<f32>>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[repr(u8)]
+pub enum TextRangeUnits {
+    #[default]
+    Percent = 1,
+    Index = 2,
+}
+
+impl DeJson for TextRangeUnits {
+    fn de_json(s: &mut DeJsonState, i: &mut std::str::Chars) -> Result<Self, DeJsonErr> {
+        match s.tok {
+            DeJsonTok::U64(_) => {
+                let r = s.as_f64()? as u8;
+                s.next_tok(i)?;
+                match r {
+                    1 => Ok(Self::Percent),
+                    2 => Ok(Self::Index),
+                    _ => Err(s.err_range("1..2")),
+                }
+            }
+            _ => Err(s.err_token("F64")),
+        }
+    }
+}
+
+impl SerJson for TextRangeUnits {
+    fn ser_json(&self, d: usize, s: &mut SerJsonState) {
+        match self {
+            Self::Percent => 1.ser_json(d, s),
+            Self::Index => 2.ser_json(d, s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 #[repr(u8)]
 pub enum TextBased {
+    #[default]
     Characters = 1,
     CharactersExcludingSpaces = 2,
     Words = 3,
@@ -2376,9 +2988,10 @@ impl SerJson for TextBased {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 #[repr(u8)]
 pub enum TextShape {
+    #[default]
     Square = 1,
     RampUp = 2,
     RampDown = 3,
@@ -2424,9 +3037,9 @@ impl SerJson for TextShape {
 #[derive(DeJson, SerJson, Debug, Clone)]
 pub struct TextStyle {
     #[nserde(rename = "sw", default)]
-    stroke_width: Option<Animated<f32>>,
+    pub stroke_width: Option<Animated<f32>>,
     #[nserde(rename = "sc", default)]
-    stroke_color: Option<Animated<Rgb>>,
+    pub stroke_color: Option<Animated<Rgb>>,
     #[nserde(rename = "sh", default)]
     stroke_hue: Option<Animated<f32>>,
     #[nserde(rename = "ss", default)]
@@ -2436,7 +3049,7 @@ pub struct TextStyle {
     #[nserde(rename = "so", default)]
     stroke_opacity: Option<Animated<f32>>,
     #[nserde(rename = "fc", default)]
-    fill_color: Option<Animated<Rgb>>,
+    pub fill_color: Option<Animated<Rgb>>,
     #[nserde(rename = "fh", default)]
     fill_hue: Option<Animated<f32>>,
     #[nserde(rename = "fs", default)]
@@ -2449,8 +3062,21 @@ pub struct TextStyle {
     blur: Option<Animated<f32>>,
     #[nserde(rename = "ls", default)]
     pub line_spacing: Option<Animated<f32>>,
-    // #[nserde(flatten)]
-    transform: Option<Transform>,
+    // The transform properties are flattened into the style, see the nanoserde TODO
+    #[nserde(rename = "a", default)]
+    pub anchor: Option<Animated<Vector2D>>,
+    #[nserde(rename = "p", default)]
+    pub position: Option<Animated<Vector2D>>,
+    #[nserde(rename = "s", default)]
+    pub scale: Option<Animated<Vector2D>>,
+    #[nserde(rename = "r", default)]
+    pub rotation: Option<Animated<f32>>,
+    #[nserde(rename = "o", default)]
+    pub opacity: Option<Animated<f32>>,
+    #[nserde(rename = "sk", default)]
+    pub skew: Option<Animated<f32>>,
+    #[nserde(rename = "sa", default)]
+    pub skew_axis: Option<Animated<f32>>,
 }
 
 #[derive(SerJson, DeJson, Debug, Clone, Default)]
@@ -2475,6 +3101,260 @@ pub struct Ellipse {
     pub size: Animated<Vector2D>,
 }
 
+#[derive(SerJson, DeJson, Debug, Clone, Default)]
+pub struct PolyStar {
+    #[nserde(rename = "d", default)]
+    pub direction: ShapeDirection,
+    #[nserde(rename = "p")]
+    pub position: Animated<Vector2D>,
+    #[nserde(rename = "or")]
+    pub outer_radius: Animated<f32>,
+    #[nserde(rename = "os")]
+    pub outer_roundness: Animated<f32>,
+    #[nserde(rename = "ir", default)]
+    pub inner_radius: Option<Animated<f32>>,
+    #[nserde(rename = "is", default)]
+    pub inner_roundness: Option<Animated<f32>>,
+    #[nserde(rename = "r")]
+    pub rotation: Animated<f32>,
+    #[nserde(rename = "pt")]
+    pub points: Animated<f32>,
+    #[nserde(rename = "sy")]
+    pub star_type: PolyStarType,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[repr(u8)]
+pub enum PolyStarType {
+    #[default]
+    Star = 1,
+    Polygon = 2,
+}
+
+impl DeJson for PolyStarType {
+    fn de_json(s: &mut DeJsonState, i: &mut std::str::Chars) -> Result<Self, DeJsonErr> {
+        match s.tok {
+            DeJsonTok::U64(_) => {
+                let r = s.as_f64()? as u8;
+                s.next_tok(i)?;
+                match r {
+                    1 => Ok(Self::Star),
+                    2 => Ok(Self::Polygon),
+                    _ => Err(s.err_range("1..2")),
+                }
+            }
+            _ => Err(s.err_token("F64")),
+        }
+    }
+}
+
+impl SerJson for PolyStarType {
+    fn ser_json(&self, d: usize, s: &mut SerJsonState) {
+        match self {
+            Self::Star => 1.ser_json(d, s),
+            Self::Polygon => 2.ser_json(d, s),
+        }
+    }
+}
+
+#[derive(SerJson, DeJson, Debug, Clone, Default)]
+pub struct Trim {
+    #[nserde(rename = "s")]
+    pub start: Animated<f32>,
+    #[nserde(rename = "e")]
+    pub end: Animated<f32>,
+    #[nserde(rename = "o")]
+    pub offset: Animated<f32>,
+    #[nserde(rename = "m", default)]
+    pub multiple_shape: TrimMultipleShape,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[repr(u8)]
+pub enum TrimMultipleShape {
+    /// Every path is trimmed on its own
+    #[default]
+    Simultaneously = 1,
+    /// All paths are trimmed as if they were one long path
+    Individually = 2,
+}
+
+impl DeJson for TrimMultipleShape {
+    fn de_json(s: &mut DeJsonState, i: &mut std::str::Chars) -> Result<Self, DeJsonErr> {
+        match s.tok {
+            DeJsonTok::U64(_) => {
+                let r = s.as_f64()? as u8;
+                s.next_tok(i)?;
+                match r {
+                    1 => Ok(Self::Simultaneously),
+                    2 => Ok(Self::Individually),
+                    _ => Err(s.err_range("1..2")),
+                }
+            }
+            _ => Err(s.err_token("F64")),
+        }
+    }
+}
+
+impl SerJson for TrimMultipleShape {
+    fn ser_json(&self, d: usize, s: &mut SerJsonState) {
+        match self {
+            Self::Simultaneously => 1.ser_json(d, s),
+            Self::Individually => 2.ser_json(d, s),
+        }
+    }
+}
+
+#[derive(SerJson, DeJson, Debug, Clone, Default)]
+pub struct Repeater {
+    #[nserde(rename = "c")]
+    pub copies: Animated<f32>,
+    #[nserde(rename = "o", default_with = "default_number_0")]
+    pub offset: Animated<f32>,
+    #[nserde(rename = "m", default)]
+    pub composite: Composite,
+    #[nserde(rename = "tr")]
+    pub transform: RepeaterTransform,
+}
+
+#[derive(SerJson, DeJson, Debug, Clone)]
+#[nserde(serialize_none_as_null)]
+pub struct RepeaterTransform {
+    #[nserde(rename = "a", default)]
+    pub anchor: Option<Animated<Vector2D>>,
+    #[nserde(rename = "p", default)]
+    pub position: Option<Animated<Vector2D>>,
+    #[nserde(rename = "s", default_with = "default_vec2_100")]
+    pub scale: Animated<Vector2D>,
+    #[nserde(rename = "r", default)]
+    pub rotation: Animated<f32>,
+    #[nserde(rename = "so", default_with = "default_number_100")]
+    pub start_opacity: Animated<f32>,
+    #[nserde(rename = "eo", default_with = "default_number_100")]
+    pub end_opacity: Animated<f32>,
+}
+
+impl Default for RepeaterTransform {
+    fn default() -> Self {
+        Self {
+            anchor: Default::default(),
+            position: Default::default(),
+            scale: default_vec2_100(),
+            rotation: Default::default(),
+            start_opacity: default_number_100(),
+            end_opacity: default_number_100(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[repr(u8)]
+pub enum Composite {
+    /// Every copy is stacked on top of the previous one
+    #[default]
+    Above = 1,
+    /// Every copy is stacked below the previous one
+    Below = 2,
+}
+
+impl DeJson for Composite {
+    fn de_json(s: &mut DeJsonState, i: &mut std::str::Chars) -> Result<Self, DeJsonErr> {
+        match s.tok {
+            DeJsonTok::U64(_) => {
+                let r = s.as_f64()? as u8;
+                s.next_tok(i)?;
+                match r {
+                    1 => Ok(Self::Above),
+                    2 => Ok(Self::Below),
+                    _ => Err(s.err_range("1..2")),
+                }
+            }
+            _ => Err(s.err_token("F64")),
+        }
+    }
+}
+
+impl SerJson for Composite {
+    fn ser_json(&self, d: usize, s: &mut SerJsonState) {
+        match self {
+            Self::Above => 1.ser_json(d, s),
+            Self::Below => 2.ser_json(d, s),
+        }
+    }
+}
+
+#[derive(SerJson, DeJson, Debug, Clone, Default)]
+pub struct Merge {
+    #[nserde(rename = "mm", default)]
+    pub mode: MergeMode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[repr(u8)]
+pub enum MergeMode {
+    /// The paths are kept as they are
+    #[default]
+    Normal = 1,
+    Add = 2,
+    Subtract = 3,
+    Intersect = 4,
+    Exclude = 5,
+}
+
+impl DeJson for MergeMode {
+    fn de_json(s: &mut DeJsonState, i: &mut std::str::Chars) -> Result<Self, DeJsonErr> {
+        match s.tok {
+            DeJsonTok::U64(_) => {
+                let r = s.as_f64()? as u8;
+                s.next_tok(i)?;
+                match r {
+                    1 => Ok(Self::Normal),
+                    2 => Ok(Self::Add),
+                    3 => Ok(Self::Subtract),
+                    4 => Ok(Self::Intersect),
+                    5 => Ok(Self::Exclude),
+                    _ => Err(s.err_range("1..5")),
+                }
+            }
+            _ => Err(s.err_token("F64")),
+        }
+    }
+}
+
+impl SerJson for MergeMode {
+    fn ser_json(&self, d: usize, s: &mut SerJsonState) {
+        match self {
+            Self::Normal => 1.ser_json(d, s),
+            Self::Add => 2.ser_json(d, s),
+            Self::Subtract => 3.ser_json(d, s),
+            Self::Intersect => 4.ser_json(d, s),
+            Self::Exclude => 5.ser_json(d, s),
+        }
+    }
+}
+
+#[derive(SerJson, DeJson, Debug, Clone, Default)]
+pub struct OffsetPath {
+    #[nserde(rename = "a")]
+    pub amount: Animated<f32>,
+    #[nserde(rename = "lj", default)]
+    pub line_join: LineJoin,
+    #[nserde(rename = "ml", default_with = "default_miter_limit")]
+    pub miter_limit: Animated<f32>,
+}
+
+#[derive(SerJson, DeJson, Debug, Clone, Default)]
+pub struct ZigZag {
+    /// Ridges per segment
+    #[nserde(rename = "r")]
+    pub frequency: Animated<f32>,
+    #[nserde(rename = "s")]
+    pub amplitude: Animated<f32>,
+    /// 1 for corners, 2 for smooth ridges
+    #[nserde(rename = "pt")]
+    pub point_type: Animated<f32>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
 #[repr(u8)]
 pub enum ShapeDirection {
@@ -2600,13 +3480,13 @@ pub struct Stroke {
     #[nserde(rename = "lj")]
     pub line_join: LineJoin,
     #[nserde(rename = "ml", default)]
-    miter_limit: f32,
+    pub miter_limit: f32,
     #[nserde(rename = "o")]
     pub opacity: Animated<f32>,
     #[nserde(rename = "w")]
     pub width: Animated<f32>,
     #[nserde(rename = "d", default)]
-    dashes: Vec<StrokeDash>,
+    pub dashes: Vec<StrokeDash>,
     #[nserde(rename = "c")]
     pub color: Animated<Rgb>,
 }
@@ -2688,9 +3568,9 @@ impl SerJson for LineJoin {
 #[derive(SerJson, DeJson, Debug, Clone)]
 pub struct StrokeDash {
     #[nserde(rename = "v")]
-    length: Animated<f32>,
+    pub length: Animated<f32>,
     #[nserde(rename = "n")]
-    ty: StrokeDashType,
+    pub ty: StrokeDashType,
 }
 
 #[derive(SerJson, DeJson, Debug, Clone, Copy)]
@@ -2712,6 +3592,19 @@ pub struct GradientFill {
     pub highlight_length: Animated<f32>,
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct GradientStroke {
+    pub line_cap: LineCap,
+    pub line_join: LineJoin,
+    pub miter_limit: f32,
+    pub opacity: Animated<f32>,
+    pub width: Animated<f32>,
+    pub dashes: Vec<StrokeDash>,
+    pub gradient: Gradient,
+    pub highlight_angle: Animated<f32>,
+    pub highlight_length: Animated<f32>,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct Gradient {
     pub start: Animated<Vector2D>,
@@ -2819,25 +3712,71 @@ fn f32_to_gradient_colors(data: &Vec<f32>, color_count: usize) -> Vec<GradientCo
                 color: Rgba::new_f32(chunk[1], chunk[2], chunk[3], 1.0),
             })
             .collect()
-    } else if data.len() == color_count * 4 + color_count * 2 {
-        // Rgba color
-        (&data[0..(color_count * 4)])
-            .chunks(4)
-            .zip((&data[(color_count * 4)..]).chunks(2))
-            .map(|(chunk, opacity)| GradientColor {
-                offset: chunk[0],
-                color: Rgba::new_f32(chunk[1], chunk[2], chunk[3], opacity[1]),
+    } else {
+        // Rgba color, the opacity stops have offsets of their own
+        let split = (color_count * 4).min(data.len());
+        let colors = data[..split].chunks_exact(4).collect::<Vec<_>>();
+        let opacities = data[split..].chunks_exact(2).collect::<Vec<_>>();
+        if colors.is_empty() {
+            return vec![];
+        }
+        // Stops at the offsets of both lists, so that no color or opacity stop is lost
+        let mut offsets = colors
+            .iter()
+            .chain(&opacities)
+            .map(|stop| stop[0])
+            .collect::<Vec<_>>();
+        offsets.sort_by(f32::total_cmp);
+        offsets.dedup_by(|a, b| (*a - *b).abs() < 1e-6);
+        offsets
+            .into_iter()
+            .map(|offset| {
+                let rgb = stop_values_at(&colors, offset);
+                let opacity = if opacities.is_empty() {
+                    1.0
+                } else {
+                    stop_values_at(&opacities, offset)[0]
+                };
+                GradientColor {
+                    offset,
+                    color: Rgba::new_f32(rgb[0], rgb[1], rgb[2], opacity),
+                }
             })
             .collect()
-    } else {
-        unimplemented!()
     }
 }
 
+/// Interpolates gradient stops (an offset followed by values) at an offset, padded beyond the first and last stop
+fn stop_values_at(stops: &[&[f32]], offset: f32) -> Vec<f32> {
+    let mut values = stops[0][1..].to_vec();
+    for pair in stops.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        if offset >= a[0] {
+            let t = if b[0] > a[0] {
+                ((offset - a[0]) / (b[0] - a[0])).min(1.0)
+            } else {
+                1.0
+            };
+            values = a[1..]
+                .iter()
+                .zip(&b[1..])
+                .map(|(a, b)| a + (b - a) * t)
+                .collect();
+        }
+    }
+    values
+}
+
 impl From<&ColorList> for ColorListHelper {
     fn from(list: &ColorList) -> Self {
+        // Separate opacity stops are merged into the colors when parsing, so there can be more stops now
+        let color_count = list
+            .colors
+            .keyframes
+            .first()
+            .map_or(list.color_count, |keyframe| keyframe.start_value.len());
         ColorListHelper {
-            color_count: list.color_count,
+            color_count,
             colors: Animated {
                 animated: list.colors.animated,
                 keyframes: list
@@ -2878,17 +3817,76 @@ fn gradient_colors_to_f32(data: &Vec<GradientColor>) -> Vec<f32> {
     start
 }
 
-// #[derive(SerJson, DeJson, Debug, Clone)]
-// pub struct TextAnimationData {
-//     #[nserde(rename = "a")]
-//     pub ranges: Vec<TextRange>,
-//     #[nserde(rename = "d")]
-//     pub document: TextData,
-//     #[nserde(rename = "m")]
-//     options: TextAlignmentOptions,
-//     #[nserde(rename = "p")]
-//     follow_path: TextFollowPath,
-// }
+#[derive(SerJson, DeJson, Debug, Clone, Default)]
+pub struct TextAnimationData {
+    #[nserde(rename = "a", default)]
+    pub ranges: Vec<TextRange>,
+    #[nserde(rename = "d")]
+    pub document: Animated<TextDocument>,
+    #[nserde(rename = "m", default)]
+    options: TextAlignmentOptions,
+    #[nserde(rename = "p", default)]
+    follow_path: TextFollowPath,
+}
+
+#[derive(SerJson, DeJson, Debug, Clone, Default)]
+pub struct TextAlignmentOptions {
+    #[nserde(rename = "a", default)]
+    alignment: Option<Animated<Vector2D>>,
+    #[nserde(rename = "g", default)]
+    grouping: TextGrouping,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(u8)]
+pub enum TextGrouping {
+    #[default]
+    Characters = 1,
+    Words = 2,
+    Lines = 3,
+    All = 4,
+}
+
+impl DeJson for TextGrouping {
+    fn de_json(s: &mut DeJsonState, i: &mut std::str::Chars) -> Result<Self, DeJsonErr> {
+        match s.tok {
+            DeJsonTok::U64(_) => {
+                let r = s.as_f64()? as u8;
+                s.next_tok(i)?;
+                match r {
+                    1 => Ok(Self::Characters),
+                    2 => Ok(Self::Words),
+                    3 => Ok(Self::Lines),
+                    4 => Ok(Self::All),
+                    _ => Err(s.err_range("1..4")),
+                }
+            }
+            _ => Err(s.err_token("F64")),
+        }
+    }
+}
+
+impl SerJson for TextGrouping {
+    fn ser_json(&self, d: usize, s: &mut SerJsonState) {
+        match self {
+            Self::Characters => 1.ser_json(d, s),
+            Self::Words => 2.ser_json(d, s),
+            Self::Lines => 3.ser_json(d, s),
+            Self::All => 4.ser_json(d, s),
+        }
+    }
+}
+
+// TODO: text along mask paths is not rendered yet
+#[derive(SerJson, DeJson, Debug, Clone, Default)]
+pub struct TextFollowPath {
+    #[nserde(rename = "m", default)]
+    mask: Option<u32>,
+    #[nserde(rename = "f", default)]
+    first_margin: Option<Animated<f32>>,
+    #[nserde(rename = "l", default)]
+    last_margin: Option<Animated<f32>>,
+}
 
 #[derive(SerJson, DeJson, Debug, Clone, Default)]
 pub struct Media {
@@ -2898,7 +3896,7 @@ pub struct Media {
     pub filename: String,
     #[nserde(rename = "e", proxy = "BoolFromInt", default)]
     pub embedded: bool,
-    id: String,
+    pub id: String,
     #[nserde(rename = "nm", default)]
     name: Option<String>,
     #[nserde(rename = "mn", default, skip)]
@@ -2916,6 +3914,21 @@ pub fn default_vec2_100() -> Animated<Vector2D> {
     }
 }
 
+pub fn default_number_0() -> Animated<f32> {
+    Animated {
+        animated: false,
+        keyframes: vec![KeyFrame::from_value(0.0)],
+    }
+}
+
+/// The default miter limit of After Effects
+pub fn default_miter_limit() -> Animated<f32> {
+    Animated {
+        animated: false,
+        keyframes: vec![KeyFrame::from_value(4.0)],
+    }
+}
+
 pub fn default_number_100() -> Animated<f32> {
     Animated {
         animated: false,
@@ -2988,6 +4001,10 @@ pub struct LegacyKeyFrame<T> {
     easing_in: Option<Easing>,
     #[nserde(rename = "h", default, proxy = "BoolFromInt")]
     hold: bool,
+    #[nserde(rename = "to", default)]
+    out_tangent: Option<Vec<f32>>,
+    #[nserde(rename = "ti", default)]
+    in_tangent: Option<Vec<f32>>,
 }
 
 impl DeJson for KeyFramesFromArray {
@@ -3050,6 +4067,8 @@ impl DeJson for KeyFramesFromArray {
                             let mut _easing_out = None;
                             let mut _easing_in = None;
                             let mut _hold = None;
+                            let mut _out_tangent = None;
+                            let mut _in_tangent = None;
                             while let Some(_) = s.next_str() {
                                 match AsRef::<str>::as_ref(&s.strbuf) {
                                     "s" => {
@@ -3076,6 +4095,14 @@ impl DeJson for KeyFramesFromArray {
                                         s.next_colon(i)?;
                                         _hold = Some(DeJson::de_json(s, i)?);
                                     }
+                                    "to" => {
+                                        s.next_colon(i)?;
+                                        _out_tangent = Some(DeJson::de_json(s, i)?);
+                                    }
+                                    "ti" => {
+                                        s.next_colon(i)?;
+                                        _in_tangent = Some(DeJson::de_json(s, i)?);
+                                    }
                                     _ => {
                                         s.next_colon(i)?;
                                         s.whole_field(i)?;
@@ -3128,6 +4155,8 @@ impl DeJson for KeyFramesFromArray {
                                         Default::default()
                                     }
                                 },
+                                out_tangent: _out_tangent,
+                                in_tangent: _in_tangent,
                             });
                         }
                         s.eat_comma_block(i)?;
@@ -3182,6 +4211,8 @@ impl<T: Clone + Default + FromTo<Value> + Debug> From<&KeyFramesFromArray> for V
                     end_frame: 0.0,
                     easing_in: None,
                     easing_out: None,
+                    out_tangent: None,
+                    in_tangent: None,
                 }]
             }
             KeyFramesFromArray::List(v) => {
@@ -3192,6 +4223,8 @@ impl<T: Clone + Default + FromTo<Value> + Debug> From<&KeyFramesFromArray> for V
                     end_frame: 0.0,
                     easing_in: None,
                     easing_out: None,
+                    out_tangent: None,
+                    in_tangent: None,
                 }]
             }
             KeyFramesFromArray::LegacyKeyFrames(v) => {
@@ -3231,6 +4264,8 @@ impl<T: Clone + Default + FromTo<Value> + Debug> From<&KeyFramesFromArray> for V
                             end_frame: keyframe.end_frame.max(keyframe.start_frame),
                             easing_in: keyframe.easing_in,
                             easing_out: keyframe.easing_out,
+                            out_tangent: keyframe.out_tangent.as_deref().map(tangent_from_slice),
+                            in_tangent: keyframe.in_tangent.as_deref().map(tangent_from_slice),
                         }
                     })
                     .collect();
@@ -3240,6 +4275,13 @@ impl<T: Clone + Default + FromTo<Value> + Debug> From<&KeyFramesFromArray> for V
     }
 }
 
+fn tangent_from_slice(v: &[f32]) -> Vector2D {
+    Vector2D::new(
+        v.first().cloned().unwrap_or(0.0),
+        v.get(1).cloned().unwrap_or(0.0),
+    )
+}
+
 #[derive(SerJson, DeJson, Debug, Clone, Default)]
 pub struct Animated<T: Debug + Default + Clone + FromTo<Value>> {
     #[nserde(proxy = "BoolFromInt", rename = "a", default)]
@@ -3263,6 +4305,12 @@ pub struct KeyFrame<T: Default + Debug> {
     pub easing_out: Option<Easing>,
     #[nserde(rename = "i", default)]
     pub easing_in: Option<Easing>,
+    /// Spatial tangent leaving `start_value`, relative to it (only used by positions)
+    #[nserde(skip)]
+    pub out_tangent: Option<Vector2D>,
+    /// Spatial tangent entering `end_value`, relative to it (only used by positions)
+    #[nserde(skip)]
+    pub in_tangent: Option<Vector2D>,
 }
 
 impl<T: Clone + Default + Debug> KeyFrame<T> {
@@ -3274,6 +4322,8 @@ impl<T: Clone + Default + Debug> KeyFrame<T> {
             end_frame: 0.0,
             easing_out: None,
             easing_in: None,
+            out_tangent: None,
+            in_tangent: None,
         }
     }
 
@@ -3285,6 +4335,8 @@ impl<T: Clone + Default + Debug> KeyFrame<T> {
             end_frame: self.end_frame,
             easing_out: self.easing_out.clone(),
             easing_in: self.easing_in.clone(),
+            out_tangent: self.out_tangent.clone(),
+            in_tangent: self.in_tangent.clone(),
         }
     }
 }
@@ -3338,3 +4390,302 @@ pub struct Easing {
     #[nserde(proxy = "ArrayFromArrayOfNumber")]
     pub y: Vec<f32>,
 }
+
+impl Easing {
+    fn control_point(&self) -> (f32, f32) {
+        (
+            self.x.first().cloned().unwrap_or(0.0).clamp(0.0, 1.0),
+            self.y.first().cloned().unwrap_or(0.0),
+        )
+    }
+}
+
+fn cubic_bezier_coordinate(p1: f32, p2: f32, t: f32) -> f32 {
+    let s = 1.0 - t;
+    3.0 * s * s * t * p1 + 3.0 * s * t * t * p2 + t * t * t
+}
+
+fn cubic_bezier_coordinate_derivative(p1: f32, p2: f32, t: f32) -> f32 {
+    let s = 1.0 - t;
+    3.0 * s * s * p1 + 6.0 * s * t * (p2 - p1) + 3.0 * t * t * (1.0 - p2)
+}
+
+/// Maps the linear progress `x` through the timing curve (0, 0), `easing_out`, `easing_in`, (1, 1)
+///
+/// Missing handles make the timing curve linear, like in lottie-web.
+pub(crate) fn ease(easing_out: Option<&Easing>, easing_in: Option<&Easing>, x: f32) -> f32 {
+    let (x1, y1) = easing_out.map(Easing::control_point).unwrap_or((0.0, 0.0));
+    let (x2, y2) = easing_in.map(Easing::control_point).unwrap_or((1.0, 1.0));
+    if x1 == y1 && x2 == y2 {
+        return x;
+    }
+    // Newton's method converges in a few steps for most curves
+    let mut t = x;
+    for _ in 0..8 {
+        let error = cubic_bezier_coordinate(x1, x2, t) - x;
+        if error.abs() < 1e-6 {
+            return cubic_bezier_coordinate(y1, y2, t);
+        }
+        let derivative = cubic_bezier_coordinate_derivative(x1, x2, t);
+        if derivative.abs() < 1e-6 {
+            break;
+        }
+        t = (t - error / derivative).clamp(0.0, 1.0);
+    }
+    // Fall back to bisection for flat parts of the curve
+    let (mut lower, mut upper) = (0.0, 1.0);
+    t = x;
+    for _ in 0..32 {
+        let value = cubic_bezier_coordinate(x1, x2, t);
+        if (value - x).abs() < 1e-6 {
+            break;
+        }
+        if value < x {
+            lower = t;
+        } else {
+            upper = t;
+        }
+        t = (lower + upper) * 0.5;
+    }
+    cubic_bezier_coordinate(y1, y2, t)
+}
+
+/// Interpolation between two values of an [Animated] property
+pub trait Lerp {
+    fn lerp(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for Vector2D {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Vector2D(self.0.lerp(other.0, t))
+    }
+}
+
+fn lerp_u8(a: u8, b: u8, t: f32) -> u8 {
+    Lerp::lerp(&(a as f32), &(b as f32), t)
+        .round()
+        .clamp(0.0, 255.0) as u8
+}
+
+impl Lerp for Rgb {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Rgb::new_u8(
+            lerp_u8(self.r, other.r, t),
+            lerp_u8(self.g, other.g, t),
+            lerp_u8(self.b, other.b, t),
+        )
+    }
+}
+
+impl Lerp for Rgba {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Rgba::new_u8(
+            lerp_u8(self.r, other.r, t),
+            lerp_u8(self.g, other.g, t),
+            lerp_u8(self.b, other.b, t),
+            lerp_u8(self.a, other.a, t),
+        )
+    }
+}
+
+impl Lerp for GradientColor {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        GradientColor {
+            offset: Lerp::lerp(&self.offset, &other.offset, t),
+            color: self.color.lerp(&other.color, t),
+        }
+    }
+}
+
+impl Lerp for Bezier {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        // Shapes with a different topology can not be morphed, so we jump at the end
+        if self.verticies.len() != other.verticies.len() {
+            return if t < 1.0 { self.clone() } else { other.clone() };
+        }
+        let lerp_points = |a: &Vec<Vector2D>, b: &Vec<Vector2D>| {
+            a.iter()
+                .zip(b.iter())
+                .map(|(a, b)| a.lerp(b, t))
+                .collect::<Vec<_>>()
+        };
+        Bezier {
+            closed: self.closed,
+            verticies: lerp_points(&self.verticies, &other.verticies),
+            in_tangent: lerp_points(&self.in_tangent, &other.in_tangent),
+            out_tangent: lerp_points(&self.out_tangent, &other.out_tangent),
+        }
+    }
+}
+
+impl Lerp for TextDocument {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        // Text documents can not be interpolated, every keyframe is held until the next one
+        if t < 1.0 {
+            self.clone()
+        } else {
+            other.clone()
+        }
+    }
+}
+
+impl<T: Lerp + Clone> Lerp for Vec<T> {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        if self.len() != other.len() {
+            return if t < 1.0 { self.clone() } else { other.clone() };
+        }
+        self.iter()
+            .zip(other.iter())
+            .map(|(a, b)| a.lerp(b, t))
+            .collect()
+    }
+}
+
+impl<T: Debug + Default + Clone + FromTo<Value> + Lerp> Animated<T> {
+    /// Returns the [KeyFrame] which is active at the given frame together with the eased progress in it,
+    /// the callers make sure that there is at least one
+    fn keyframe_at(&self, frame: f32) -> (&KeyFrame<T>, f32) {
+        let keyframe = match self
+            .keyframes
+            .iter()
+            .rev()
+            .find(|keyframe| keyframe.start_frame <= frame)
+        {
+            Some(keyframe) => keyframe,
+            None => return (&self.keyframes[0], 0.0),
+        };
+        if keyframe.end_frame <= keyframe.start_frame || frame >= keyframe.end_frame {
+            return (keyframe, 1.0);
+        }
+        let progress = (frame - keyframe.start_frame) / (keyframe.end_frame - keyframe.start_frame);
+        (
+            keyframe,
+            ease(
+                keyframe.easing_out.as_ref(),
+                keyframe.easing_in.as_ref(),
+                progress,
+            ),
+        )
+    }
+
+    /// Evaluates the property at the given (possibly fractional) frame.
+    ///
+    /// Frames before the first keyframe get its start value and frames after the last keyframe get its end value.
+    /// Hold keyframes need no special treatment here, because they already carry
+    /// their start value as end value (see `From<&KeyFramesFromArray>`).
+    /// Properties without keyframes, like omitted optional ones, evaluate to the default value.
+    pub fn value_at(&self, frame: f32) -> T {
+        let Some(first) = self.keyframes.first() else {
+            return T::default();
+        };
+        if !self.animated || self.keyframes.len() == 1 {
+            return first.start_value.clone();
+        }
+        let (keyframe, t) = self.keyframe_at(frame);
+        keyframe.start_value.lerp(&keyframe.end_value, t)
+    }
+}
+
+/// Number of samples used to approximate the arc length of spatial bezier segments
+const SPATIAL_BEZIER_SAMPLES: usize = 64;
+
+fn cubic_bezier_point(points: &[Vector2D; 4], t: f32) -> Vector2D {
+    let s = 1.0 - t;
+    let p = points[0].0 * (s * s * s)
+        + points[1].0 * (3.0 * s * s * t)
+        + points[2].0 * (3.0 * s * t * t)
+        + points[3].0 * (t * t * t);
+    Vector2D(p)
+}
+
+impl Animated<Vector2D> {
+    /// Evaluates a position property at the given frame, following the motion path.
+    ///
+    /// Unlike [Animated::value_at] this respects the spatial tangents (`to`, `ti`) of the keyframes.
+    /// The eased progress is interpreted as the travelled fraction of the arc length,
+    /// so the speed along the curve only depends on the easing and not on the control points.
+    pub fn spatial_value_at(&self, frame: f32) -> Vector2D {
+        let Some(first) = self.keyframes.first() else {
+            return Vector2D::default();
+        };
+        if !self.animated || self.keyframes.len() == 1 {
+            return first.start_value.clone();
+        }
+        let (keyframe, t) = self.keyframe_at(frame);
+        let zero = Vector2D::default();
+        let out_tangent = keyframe.out_tangent.as_ref().unwrap_or(&zero);
+        let in_tangent = keyframe.in_tangent.as_ref().unwrap_or(&zero);
+        if keyframe.start_value == keyframe.end_value
+            || (*out_tangent == zero && *in_tangent == zero)
+        {
+            return keyframe.start_value.lerp(&keyframe.end_value, t);
+        }
+        let points = [
+            keyframe.start_value.clone(),
+            Vector2D(keyframe.start_value.0 + out_tangent.0),
+            Vector2D(keyframe.end_value.0 + in_tangent.0),
+            keyframe.end_value.clone(),
+        ];
+        let mut lengths = [0.0; SPATIAL_BEZIER_SAMPLES + 1];
+        let mut previous = points[0].clone();
+        for i in 1..=SPATIAL_BEZIER_SAMPLES {
+            let point = cubic_bezier_point(&points, i as f32 / SPATIAL_BEZIER_SAMPLES as f32);
+            lengths[i] = lengths[i - 1] + (point.0 - previous.0).length();
+            previous = point;
+        }
+        let target = lengths[SPATIAL_BEZIER_SAMPLES] * t.clamp(0.0, 1.0);
+        let index = lengths
+            .iter()
+            .position(|length| *length >= target)
+            .unwrap_or(SPATIAL_BEZIER_SAMPLES)
+            .max(1);
+        let segment_length = lengths[index] - lengths[index - 1];
+        let segment_t = if segment_length > 0.0 {
+            (target - lengths[index - 1]) / segment_length
+        } else {
+            0.0
+        };
+        cubic_bezier_point(
+            &points,
+            (index as f32 - 1.0 + segment_t) / SPATIAL_BEZIER_SAMPLES as f32,
+        )
+    }
+}
+
+impl Transform {
+    /// Returns the position at the given frame, following the motion path of the keyframes
+    pub fn position_at(&self, frame: f32) -> Vector2D {
+        self.position
+            .as_ref()
+            .map(|position| position.spatial_value_at(frame))
+            .unwrap_or_default()
+    }
+
+    /// Returns the direction of motion (in degrees) at the given frame, which auto-orient adds to the rotation.
+    ///
+    /// Like in lottie-web the direction is measured over a short step along the motion path,
+    /// which keeps the direction of the first and last keyframe before and after the animation.
+    pub fn orientation_at(&self, frame: f32) -> f32 {
+        let position = match &self.position {
+            Some(position) if position.animated && position.keyframes.len() > 1 => position,
+            _ => return 0.0,
+        };
+        let first_frame = position.keyframes[0].start_frame;
+        let last_frame = position.keyframes[position.keyframes.len() - 1].start_frame;
+        let (from, to) = if frame <= first_frame {
+            (first_frame, first_frame + 0.01)
+        } else if frame >= last_frame {
+            (last_frame - 0.05, last_frame)
+        } else {
+            (frame - 0.01, frame)
+        };
+        let direction = position.spatial_value_at(to).0 - position.spatial_value_at(from).0;
+        direction.y.atan2(direction.x).to_degrees()
+    }
+}
@@ -1,10 +1,44 @@
+mod geometry;
+mod image;
+mod mask;
+mod modifiers;
+pub mod paint;
+mod text;
+mod transform;
+
 use {
-    crate::model::{LayerContent, Model, Shape},
+    crate::{
+        model::{
+            Asset, BlendMode, Composite, FillRule as LottieFillRule, Layer, LayerContent, LineCap,
+            LineJoin, MatteMode, Media, MediaRef, Model, PreCompositionRef, Rgba, Shape,
+            ShapeLayer, StrokeDash, StrokeDashType, TextAnimationData, Vector2D,
+        },
+        path_rendering::{
+            boolean::{combine_areas, BooleanOperation},
+            path::{
+                Cap, CurveApproximation, DashInterval, DynamicStrokeOptions, Join, Path,
+                StrokeOptions, MAX_DASH_INTERVALS,
+            },
+            renderer::{Blending, FillRule, RenderOperation},
+            ttf_parser, OPEN_SANS_TTF,
+        },
+    },
+    geometry::{ellipse_path, paths_from_beziers, polystar_path, rectangle_path},
+    image::media_data,
+    mask::mask_area,
+    modifiers::{
+        merge_paths, offset_paths, pucker_bloat_paths, rounded_corners_paths, trim_paths,
+        twist_paths, zig_zag_paths,
+    },
     // lottie::prelude::Bezier,
-    macroquad::prelude::*,
     nanoserde::{DeJson, SerJson},
+    paint::{ImagePaint, Paint},
     serde_json,
     std::fs,
+    text::{animate_glyphs, layout_text},
+    transform::{
+        average_scale, compose, identity, repeater_copies, scaling, transform_matrix, Matrix,
+    },
 };
 
 pub fn load_lottie_file(compare_with_serde: bool) -> Model {
@@ -40,93 +74,804 @@ pub fn load_lottie_file(compare_with_serde: bool) -> Model {
     ns_model
 }
 
-fn draw_lottie(model: &Model) {
-    for layer in model.layers.iter().rev() {
-        match &layer.content {
-            LayerContent::Shape(shape) => {
-                let mut _fill = None;
-                let mut _gradient_fill = None;
-                let mut _stroke = None;
-
-                for shape in shape.shapes.iter().rev() {
-                    match &shape.shape {
-                        Shape::Fill(fill) => _fill = Some(fill.clone()),
-                        Shape::GradientFill(fill) => _gradient_fill = Some(fill.clone()),
-                        Shape::Stroke(stroke) => _stroke = Some(stroke.clone()),
-
-                        Shape::Rectangle(rectangle) => {
-                            if !rectangle.position.animated && !rectangle.size.animated {
-                                let x = &rectangle.position.keyframes[0].start_value.0.x;
-                                let y = &rectangle.position.keyframes[0].start_value.0.y;
-                                let w = &rectangle.size.keyframes[0].start_value.0.x;
-                                let h = &rectangle.size.keyframes[0].start_value.0.y;
-                                if let Some(fill) = &_fill {
-                                    if !fill.color.animated {
-                                        let color = fill.color.keyframes[0].start_value;
-                                        let color =
-                                            Color::from_rgba(color.r, color.g, color.b, 255);
-                                        draw_rectangle(*x - *w / 2., *y - *h / 2., *w, *h, color);
-                                    }
-                                }
-                            }
-                        }
-                        Shape::Path {
-                            data, direction, ..
-                        } => {
-                            if !data.animated {
-                                let bezier = &data.keyframes[0].start_value;
-                                if let Some(fill) = &_fill {
-                                    if !fill.color.animated {
-                                        let color = fill.color.keyframes[0].start_value;
-                                        let color =
-                                            Color::from_rgba(color.r, color.g, color.b, 255);
-                                        draw_rectangle(1., 1., 20., 20., color);
-
-                                        // let mut prev_p: Option<Vector2D>;
-                                        // match b.verticies.first() {
-                                        //     Some(p) => {
-                                        //         builder.begin(p.to_point());
-                                        //         prev_p = Some(*p);
-                                        //     }
-                                        //     None => continue,
-                                        // }
-                                        // for ((p, c1), c2) in b
-                                        //     .verticies
-                                        //     .iter()
-                                        //     .skip(1)
-                                        //     .zip(b.out_tangent.iter())
-                                        //     .zip(b.in_tangent.iter().skip(1))
-                                        // {
-                                        //     if let Some(p0) = prev_p {
-                                        //         let p1 = p0 + *c1;
-                                        //         let p2 = *p + *c2;
-                                        //         if c1.approx_eq(&Vector2D::zero()) && c2.approx_eq(&Vector2D::zero()) {
-                                        //             builder.line_to(p.to_point());
-                                        //         } else if p1.approx_eq(&p2) {
-                                        //             builder.quadratic_bezier_to(p1.to_point(), p.to_point());
-                                        //         } else {
-                                        //             builder.cubic_bezier_to(p1.to_point(), p2.to_point(), p.to_point());
-                                        //         }
-                                        //     }
-                                        //     prev_p = Some(*p);
-                                        // }
-                                        // if b.closed {
-                                        //     let index = b.verticies.len() - 1;
-                                        //     builder.cubic_bezier_to(
-                                        //         (b.verticies[index] + b.out_tangent[index]).to_point(),
-                                        //         (b.verticies[0] + b.in_tangent[0]).to_point(),
-                                        //         b.verticies[0].to_point(),
-                                        //     );
-                                        // }
-                                    }
-                                }
-                            }
-                        }
-                        _ => unimplemented!(),
+/// Inconsistencies of a [Model] which are only detected while rendering it
+#[derive(Debug)]
+pub enum Error {
+    /// A layer references a precomposition id which is not in the assets.
+    MissingPrecomposition(String),
+    /// A precomposition contains itself, directly or through other precompositions.
+    PrecompositionCycle(String),
+    /// A layer references a parent index which is not in its composition.
+    MissingParent(u32),
+    /// A layer is its own ancestor.
+    ParentCycle(u32),
+    /// A layer with a track matte references a matte index which is not in its composition,
+    /// or has no layer above it.
+    MissingMatte(Option<u32>),
+    /// A layer references an image id which is not in the assets.
+    MissingImage(String),
+    /// The image asset with this id can not be read or decoded.
+    InvalidImage(String),
+    /// The layer with this index has a content type which can not be rendered.
+    UnsupportedLayer(Option<u32>),
+}
+
+/// Geometry which is ready to be handed to the path renderer
+#[derive(Clone)]
+pub struct DrawCommand {
+    /// Stroked if the [Path]s have [StrokeOptions], filled otherwise
+    pub paths: Vec<Path>,
+    pub paint: Paint,
+    /// [RenderOperation::Color] paints the [Path]s,
+    /// [RenderOperation::Clip] limits the following [DrawCommand]s to their area until [RenderOperation::UnClip]
+    pub operation: RenderOperation,
+    /// Only fixed-function [Blending]s, the others are resolved in blend contexts
+    pub blending: Blending,
+    /// Selected by the [StrokeOptions] of stroked [Path]s
+    pub dynamic_stroke_options: Vec<DynamicStrokeOptions>,
+    pub fill_rule: FillRule,
+}
+
+/// A [DrawCommand] which only changes the state of the renderer, its paint is unused
+fn state_command(paths: Vec<Path>, operation: RenderOperation) -> DrawCommand {
+    DrawCommand {
+        paths,
+        paint: Paint::Solid([0.0; 4]),
+        operation,
+        blending: Blending::Normal,
+        dynamic_stroke_options: vec![],
+        fill_rule: FillRule::NonZero,
+    }
+}
+
+/// The [FillRule] of a Lottie fill
+fn fill_rule(fill_rule: LottieFillRule) -> FillRule {
+    match fill_rule {
+        LottieFillRule::NonZero => FillRule::NonZero,
+        LottieFillRule::EvenOdd => FillRule::EvenOdd,
+    }
+}
+
+/// The [DynamicStrokeOptions] of a Lottie stroke, its paths use group 0.
+///
+/// Dash lengths are converted into units of the stroke width, every dash gets the line cap at both ends.
+fn stroke_style(
+    line_join: LineJoin,
+    line_cap: LineCap,
+    dashes: &[StrokeDash],
+    width: f32,
+    frame: f32,
+) -> DynamicStrokeOptions {
+    let join = match line_join {
+        LineJoin::Miter => Join::Miter,
+        LineJoin::Round => Join::Round,
+        LineJoin::Bevel => Join::Bevel,
+    };
+    let cap = match line_cap {
+        LineCap::Butt => Cap::Butt,
+        LineCap::Round => Cap::Round,
+        LineCap::Square => Cap::Square,
+    };
+    let mut lengths = vec![];
+    let mut offset = 0.0;
+    for dash in dashes {
+        match dash.ty {
+            StrokeDashType::Dash | StrokeDashType::Gap => {
+                lengths.push(dash.length.value_at(frame).max(0.0))
+            }
+            StrokeDashType::Offset => offset = dash.length.value_at(frame),
+        }
+    }
+    if lengths.is_empty() || width <= 0.0 {
+        return DynamicStrokeOptions::Solid {
+            join,
+            start: cap,
+            end: cap,
+        };
+    }
+    // An odd number of lengths is repeated to alternate dashes and gaps, like in SVG
+    if lengths.len() % 2 == 1 {
+        lengths.extend_from_within(..);
+    }
+    // The renderer only supports a limited number of intervals, the rest of the pattern is dropped
+    lengths.truncate(MAX_DASH_INTERVALS * 2);
+    let mut position = 0.0;
+    let pattern = lengths
+        .chunks_exact(2)
+        .map(|dash_and_gap| {
+            let gap_start = position + dash_and_gap[0] / width;
+            position = gap_start + dash_and_gap[1] / width;
+            DashInterval {
+                gap_start: gap_start.into(),
+                gap_end: position.into(),
+                dash_start: cap,
+                dash_end: cap,
+            }
+        })
+        .collect();
+    DynamicStrokeOptions::Dashed {
+        join,
+        pattern,
+        phase: (-offset / width).into(),
+    }
+}
+
+fn stroke_paths(paths: &[Path], width: f32, miter_limit: f32) -> Vec<Path> {
+    paths
+        .iter()
+        .map(|path| {
+            let mut path = path.clone();
+            let mut stroke_options = StrokeOptions {
+                width: width.into(),
+                offset: 0.0.into(),
+                // The miter limit is relative to the width, while the clip distance is relative to the half width
+                // Round joins are cut out of the miter polygon, so it has to reach at least half the width
+                miter_clip: (miter_limit * 0.5).max(0.5).into(),
+                closed: path.is_closed(),
+                dynamic_stroke_options_group: 0,
+                curve_approximation: CurveApproximation::UniformTangentAngle(0.1.into()),
+            };
+            stroke_options.legalize();
+            path.stroke_options = Some(stroke_options);
+            path
+        })
+        .collect()
+}
+
+/// Applies a [Matrix] to [DrawCommand]s and multiplies their opacity
+fn transform_commands(commands: &mut [DrawCommand], matrix: &Matrix, opacity: f32) {
+    for command in commands {
+        transform_paths(&mut command.paths, matrix);
+        command.paint.transform(matrix, opacity);
+    }
+}
+
+/// Applies a [Matrix] to [Path]s, stroke widths scale along
+fn transform_paths(paths: &mut [Path], matrix: &Matrix) {
+    let scale = average_scale(matrix);
+    for path in paths {
+        path.transform_affine(matrix);
+        if let Some(stroke_options) = &mut path.stroke_options {
+            stroke_options.width = (stroke_options.width.unwrap() * scale).into();
+        }
+    }
+}
+
+/// Applies a modifier which works path by path to each area on its own
+fn modify_areas(
+    areas: Vec<Vec<Path>>,
+    modifier: impl Fn(Vec<Path>) -> Vec<Path>,
+) -> Vec<Vec<Path>> {
+    areas.into_iter().map(modifier).collect()
+}
+
+/// A style which paints the areas in front of it once every modifier of its group has run
+struct PendingStyle<'a> {
+    shape: &'a Shape,
+    /// Number of areas in front of the style
+    area_count: usize,
+    /// Position of its [DrawCommand], so that it stays between the commands of the other shapes
+    command_index: usize,
+}
+
+/// Builds the [DrawCommand] of a style which paints the given areas
+fn style_command(shape: &Shape, areas: &[Vec<Path>], frame: f32) -> Option<DrawCommand> {
+    Some(match shape {
+        Shape::Fill(fill) => DrawCommand {
+            paths: areas.concat(),
+            paint: Paint::solid(fill.color.value_at(frame), fill.opacity.value_at(frame)),
+            operation: RenderOperation::Color,
+            blending: Blending::Normal,
+            dynamic_stroke_options: vec![],
+            fill_rule: fill_rule(fill.fill_rule),
+        },
+        Shape::GradientFill(fill) => DrawCommand {
+            paths: areas.concat(),
+            paint: Paint::gradient(
+                &fill.gradient,
+                fill.highlight_length.value_at(frame),
+                fill.highlight_angle.value_at(frame),
+                fill.opacity.value_at(frame),
+                frame,
+            ),
+            operation: RenderOperation::Color,
+            blending: Blending::Normal,
+            dynamic_stroke_options: vec![],
+            fill_rule: fill_rule(fill.fill_rule),
+        },
+        Shape::Stroke(stroke) => {
+            let width = stroke.width.value_at(frame);
+            DrawCommand {
+                paths: stroke_paths(&areas.concat(), width, stroke.miter_limit),
+                paint: Paint::solid(stroke.color.value_at(frame), stroke.opacity.value_at(frame)),
+                operation: RenderOperation::Color,
+                blending: Blending::Normal,
+                dynamic_stroke_options: vec![stroke_style(
+                    stroke.line_join,
+                    stroke.line_cap,
+                    &stroke.dashes,
+                    width,
+                    frame,
+                )],
+                fill_rule: FillRule::NonZero,
+            }
+        }
+        Shape::GradientStroke(stroke) => {
+            let width = stroke.width.value_at(frame);
+            DrawCommand {
+                paths: stroke_paths(&areas.concat(), width, stroke.miter_limit),
+                paint: Paint::gradient(
+                    &stroke.gradient,
+                    stroke.highlight_length.value_at(frame),
+                    stroke.highlight_angle.value_at(frame),
+                    stroke.opacity.value_at(frame),
+                    frame,
+                ),
+                operation: RenderOperation::Color,
+                blending: Blending::Normal,
+                dynamic_stroke_options: vec![stroke_style(
+                    stroke.line_join,
+                    stroke.line_cap,
+                    &stroke.dashes,
+                    width,
+                    frame,
+                )],
+                fill_rule: FillRule::NonZero,
+            }
+        }
+        _ => return None,
+    })
+}
+
+/// Inserts the [DrawCommand]s of the pending styles at their positions
+fn paint_styles(
+    styles: &mut Vec<PendingStyle>,
+    areas: &[Vec<Path>],
+    frame: f32,
+    commands: &mut Vec<DrawCommand>,
+) {
+    // Later positions first, so that the earlier ones stay valid
+    for style in styles.drain(..).rev() {
+        if let Some(command) = style_command(style.shape, &areas[..style.area_count], frame) {
+            commands.insert(style.command_index, command);
+        }
+    }
+}
+
+/// Evaluates the shapes of a group in list order and appends their [DrawCommand]s topmost first.
+///
+/// Geometry accumulates as one area per shape, modifiers alter everything accumulated so far.
+/// Styles paint the areas in front of them, but only after all modifiers of the group have run,
+/// so that a modifier listed after a style changes its geometry as well.
+/// Returns the accumulated areas, so that styles and modifiers of the enclosing group apply to them too.
+fn render_shapes(
+    shapes: &[ShapeLayer],
+    frame: f32,
+    commands: &mut Vec<DrawCommand>,
+) -> Vec<Vec<Path>> {
+    let first_command = commands.len();
+    let mut areas: Vec<Vec<Path>> = vec![];
+    let mut styles: Vec<PendingStyle> = vec![];
+    for shape in shapes.iter().filter(|shape| !shape.hidden) {
+        match &shape.shape {
+            Shape::Fill(_)
+            | Shape::GradientFill(_)
+            | Shape::Stroke(_)
+            | Shape::GradientStroke(_) => styles.push(PendingStyle {
+                shape: &shape.shape,
+                area_count: areas.len(),
+                command_index: commands.len(),
+            }),
+            Shape::Rectangle(rectangle) => areas.push(vec![rectangle_path(rectangle, frame)]),
+            Shape::Ellipse(ellipse) => areas.push(vec![ellipse_path(ellipse, frame)]),
+            Shape::PolyStar(star) => areas.push(vec![polystar_path(star, frame)]),
+            Shape::Path {
+                data, direction, ..
+            } => areas.push(paths_from_beziers(&data.value_at(frame), *direction)),
+            Shape::Group { shapes } => {
+                let group_first_command = commands.len();
+                let mut group_areas = render_shapes(shapes, frame, commands);
+                let transform = shapes.iter().find_map(|shape| match &shape.shape {
+                    Shape::Transform(transform) => Some(transform),
+                    _ => None,
+                });
+                if let Some(transform) = transform {
+                    let matrix = transform_matrix(transform, frame);
+                    let opacity = (transform.opacity.value_at(frame) / 100.0).clamp(0.0, 1.0);
+                    transform_commands(&mut commands[group_first_command..], &matrix, opacity);
+                    for area in &mut group_areas {
+                        transform_paths(area, &matrix);
                     }
                 }
+                areas.append(&mut group_areas);
+            }
+            // Applied by the enclosing group
+            Shape::Transform(_) => {}
+            Shape::Trim(trim) => areas = trim_paths(areas, trim, frame),
+            Shape::OffsetPath(offset) => {
+                areas = modify_areas(areas, |paths| offset_paths(paths, offset, frame))
+            }
+            Shape::ZigZag(zig_zag) => {
+                areas = modify_areas(areas, |paths| zig_zag_paths(paths, zig_zag, frame))
+            }
+            Shape::Merge(merge) => {
+                areas = vec![merge_paths(areas, merge)];
+                // The styles in front of the merge paint the merged area
+                for style in &mut styles {
+                    style.area_count = style.area_count.min(1);
+                }
+            }
+            Shape::RoundedCorners { radius } => {
+                let radius = radius.value_at(frame);
+                areas = modify_areas(areas, |paths| rounded_corners_paths(paths, radius))
+            }
+            Shape::PuckerBloat { amount } => {
+                let amount = amount.value_at(frame);
+                areas = modify_areas(areas, |paths| pucker_bloat_paths(paths, amount))
+            }
+            Shape::Twist { angle, center } => {
+                let (angle, center) = (angle.value_at(frame), center.value_at(frame));
+                areas = modify_areas(areas, |paths| {
+                    twist_paths(paths, angle, [center.0.x, center.0.y])
+                })
+            }
+            Shape::Repeater(repeater) => {
+                // The styles in front of the repeater are repeated along
+                paint_styles(&mut styles, &areas, frame, commands);
+                let original_commands = commands.split_off(first_command);
+                let original_areas = std::mem::take(&mut areas);
+                let mut copies = repeater_copies(repeater, frame);
+                // Commands are collected topmost first
+                if repeater.composite == Composite::Above {
+                    copies.reverse();
+                }
+                for (matrix, opacity) in copies {
+                    let mut copy_commands = original_commands.clone();
+                    transform_commands(&mut copy_commands, &matrix, opacity);
+                    commands.append(&mut copy_commands);
+                    let mut copy_areas = original_areas.clone();
+                    for area in &mut copy_areas {
+                        transform_paths(area, &matrix);
+                    }
+                    areas.append(&mut copy_areas);
+                }
+            }
+        }
+    }
+    paint_styles(&mut styles, &areas, frame, commands);
+    areas
+}
+
+/// Miter limit of text outlines, which is the default of After Effects
+const TEXT_MITER_LIMIT: f32 = 4.0;
+
+/// Glyph outlines are closed, so only the join matters
+const TEXT_STROKE_STYLE: DynamicStrokeOptions = DynamicStrokeOptions::Solid {
+    join: Join::Miter,
+    start: Cap::Butt,
+    end: Cap::Butt,
+};
+
+/// Appends the paths to the last command if it has the same color, so that uniformly colored text stays one command
+fn push_glyph_command(commands: &mut Vec<DrawCommand>, mut paths: Vec<Path>, color: [f32; 4]) {
+    if let Some(DrawCommand {
+        paths: last_paths,
+        paint: Paint::Solid(last_color),
+        operation: RenderOperation::Color,
+        blending: Blending::Normal,
+        fill_rule: FillRule::NonZero,
+        ..
+    }) = commands.last_mut()
+    {
+        if *last_color == color {
+            last_paths.append(&mut paths);
+            return;
+        }
+    }
+    commands.push(DrawCommand {
+        paths,
+        paint: Paint::Solid(color),
+        operation: RenderOperation::Color,
+        blending: Blending::Normal,
+        dynamic_stroke_options: vec![TEXT_STROKE_STYLE],
+        fill_rule: FillRule::NonZero,
+    });
+}
+
+/// Lays out and animates the document of a text layer at the given frame and appends its [DrawCommand]s topmost first.
+fn render_text(text: &TextAnimationData, frame: f32, commands: &mut Vec<DrawCommand>) {
+    let document = text.document.value_at(frame);
+    // Lottie files only reference fonts by name, so every text is set in the bundled face for now
+    let face = ttf_parser::Face::from_slice(OPEN_SANS_TTF, 0).unwrap();
+    let glyphs = layout_text(&face, &document);
+    let mut fills = vec![];
+    let mut strokes = vec![];
+    for glyph in animate_glyphs(&glyphs, &text.ranges, &document, frame) {
+        if glyph.stroke_width > 0.0 {
+            let paths = stroke_paths(&glyph.paths, glyph.stroke_width, TEXT_MITER_LIMIT);
+            push_glyph_command(&mut strokes, paths, glyph.stroke_color);
+        }
+        push_glyph_command(&mut fills, glyph.paths, glyph.fill_color);
+    }
+    if document.stroke_above_fill {
+        commands.append(&mut strokes);
+        commands.append(&mut fills);
+    } else {
+        commands.append(&mut fills);
+        commands.append(&mut strokes);
+    }
+}
+
+/// Finds the [Precomposition] a layer references and evaluates its layers at the time of the layer.
+///
+/// Time remapping replaces the time of the layer, its keyframes are in seconds.
+/// Otherwise the time of the layer is slowed down by its time stretch.
+/// The layers are clipped to the width and height of the reference, which span from the origin.
+fn render_precomposition<'a>(
+    model: &'a Model,
+    layer: &'a Layer,
+    reference: &'a PreCompositionRef,
+    frame: f32,
+    precompositions: &mut Vec<&'a str>,
+    commands: &mut Vec<DrawCommand>,
+) -> Result<(), Error> {
+    let precomposition = model
+        .assets
+        .iter()
+        .find_map(|asset| match asset {
+            Asset::Precomposition(precomposition) if precomposition.id == reference.ref_id => {
+                Some(precomposition)
+            }
+            _ => None,
+        })
+        .ok_or_else(|| Error::MissingPrecomposition(reference.ref_id.clone()))?;
+    if precompositions.contains(&precomposition.id.as_str()) {
+        return Err(Error::PrecompositionCycle(precomposition.id.clone()));
+    }
+    let child_frame = match &reference.time_remapping {
+        Some(time_remapping) => time_remapping.value_at(frame) * model.frame_rate,
+        None => frame / layer.time_stretch.unwrap_or(1.0),
+    };
+    let first_command = commands.len();
+    precompositions.push(&precomposition.id);
+    let result = render_layers(
+        model,
+        &precomposition.layers,
+        child_frame,
+        precompositions,
+        commands,
+    );
+    precompositions.pop();
+    // The layers of the precomposition are only visible inside of its width and height
+    if commands.len() > first_command {
+        let half_extent = [reference.width as f32 * 0.5, reference.height as f32 * 0.5];
+        // Commands are collected topmost first, so the clip is pushed after the content
+        commands.insert(
+            first_command,
+            state_command(vec![], RenderOperation::UnClip),
+        );
+        commands.push(state_command(
+            vec![Path::from_rect(half_extent, half_extent)],
+            RenderOperation::Clip,
+        ));
+    }
+    result
+}
+
+/// Finds the image [Media] asset with the given id
+fn find_media<'a>(model: &'a Model, id: &str) -> Result<&'a Media, Error> {
+    model
+        .assets
+        .iter()
+        .find_map(|asset| match asset {
+            Asset::Media(media) if media.id == id => Some(media),
+            _ => None,
+        })
+        .ok_or_else(|| Error::MissingImage(id.to_string()))
+}
+
+/// Reads the encoded data of all image assets, keyed by their id, so that they can be uploaded once.
+///
+/// Each image succeeds or fails on its own, so that one unreadable file does not prevent the others.
+pub fn load_images(model: &Model) -> Vec<(String, Result<Vec<u8>, Error>)> {
+    model
+        .assets
+        .iter()
+        .filter_map(|asset| match asset {
+            Asset::Media(media) => Some((media.id.clone(), media_data(media))),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Fills the rectangle of a solid color layer, which spans from the origin to its width and height
+fn render_solid_color(color: &Rgba, width: f32, height: f32, commands: &mut Vec<DrawCommand>) {
+    let half_extent = [width * 0.5, height * 0.5];
+    commands.push(DrawCommand {
+        paths: vec![Path::from_rect(half_extent, half_extent)],
+        paint: Paint::Solid([
+            color.r as f32 / 255.0,
+            color.g as f32 / 255.0,
+            color.b as f32 / 255.0,
+            color.a as f32 / 255.0,
+        ]),
+        operation: RenderOperation::Color,
+        blending: Blending::Normal,
+        dynamic_stroke_options: vec![],
+        fill_rule: FillRule::NonZero,
+    });
+}
+
+/// Draws the image a layer references in its pixel size, which spans from the origin to its width and height
+fn render_image(
+    model: &Model,
+    reference: &MediaRef,
+    commands: &mut Vec<DrawCommand>,
+) -> Result<(), Error> {
+    let media = find_media(model, &reference.ref_id)?;
+    let (Some(width), Some(height)) = (media.width, media.height) else {
+        return Err(Error::InvalidImage(media.id.clone()));
+    };
+    let half_extent = [width as f32 * 0.5, height as f32 * 0.5];
+    commands.push(DrawCommand {
+        paths: vec![Path::from_rect(half_extent, half_extent)],
+        paint: Paint::Image(ImagePaint {
+            asset_id: media.id.clone(),
+            // Scale factors are percentages
+            matrix: scaling(Vector2D::new(width as f32 * 100.0, height as f32 * 100.0)),
+            opacity: 1.0,
+        }),
+        operation: RenderOperation::Color,
+        blending: Blending::Normal,
+        dynamic_stroke_options: vec![],
+        fill_rule: FillRule::NonZero,
+    });
+    Ok(())
+}
+
+/// Composes the matrix of a layer with the matrices of its parents at the given frame of their composition.
+///
+/// Parents only pass on their matrix, not their opacity.
+fn layer_matrix(layers: &[Layer], layer: &Layer, frame: f32) -> Result<Matrix, Error> {
+    let mut matrix = identity();
+    let mut ancestors = layer.index.into_iter().collect::<Vec<_>>();
+    let mut current = layer;
+    loop {
+        if let Some(transform) = &current.transform {
+            matrix = compose(&[
+                transform_matrix(transform, frame - current.start_time),
+                matrix,
+            ]);
+        }
+        let Some(parent_index) = current.parent_index else {
+            return Ok(matrix);
+        };
+        if ancestors.contains(&parent_index) {
+            return Err(Error::ParentCycle(parent_index));
+        }
+        ancestors.push(parent_index);
+        current = layers
+            .iter()
+            .find(|layer| layer.index == Some(parent_index))
+            .ok_or(Error::MissingParent(parent_index))?;
+    }
+}
+
+/// Evaluates a single layer at the given frame and appends its [DrawCommand]s topmost first.
+///
+/// In and out points are in the time of the composition, so that the time stretch of
+/// a precomposition layer also stretches the time in which its layers are visible.
+fn render_layer<'a>(
+    model: &'a Model,
+    layers: &'a [Layer],
+    layer: &'a Layer,
+    frame: f32,
+    precompositions: &mut Vec<&'a str>,
+    commands: &mut Vec<DrawCommand>,
+) -> Result<(), Error> {
+    // Layers only exist between their in and out point
+    if frame < layer.start_frame || frame >= layer.end_frame {
+        return Ok(());
+    }
+    // The keyframes of a layer are relative to its start time
+    let layer_frame = frame - layer.start_time;
+    let first_command = commands.len();
+    match &layer.content {
+        LayerContent::Shape(shape) => {
+            render_shapes(&shape.shapes, layer_frame, commands);
+        }
+        LayerContent::Text(text) => render_text(text, layer_frame, commands),
+        LayerContent::PreCompositionRef(reference) => render_precomposition(
+            model,
+            layer,
+            reference,
+            layer_frame,
+            precompositions,
+            commands,
+        )?,
+        LayerContent::SolidColor {
+            color,
+            width,
+            height,
+        } => render_solid_color(color, *width, *height, commands),
+        LayerContent::MediaRef(reference) => render_image(model, reference, commands)?,
+        // Null layers have no content, they only pass on their matrix to their children
+        LayerContent::Empty => {}
+        LayerContent::Media(_) => return Err(Error::UnsupportedLayer(layer.index)),
+    }
+    // Masks are in the coordinate system of the layer, so they are transformed along with its content
+    if let Some((area, mask_opacity)) = mask_area(&layer.masks_properties, layer_frame) {
+        if commands.len() > first_command {
+            transform_commands(&mut commands[first_command..], &identity(), mask_opacity);
+            // Commands are collected topmost first, so the clip is pushed after the content
+            commands.insert(
+                first_command,
+                state_command(vec![], RenderOperation::UnClip),
+            );
+            commands.push(state_command(area, RenderOperation::Clip));
+        }
+    }
+    let matrix = layer_matrix(layers, layer, frame)?;
+    let opacity = layer.transform.as_ref().map_or(1.0, |transform| {
+        (transform.opacity.value_at(layer_frame) / 100.0).clamp(0.0, 1.0)
+    });
+    transform_commands(&mut commands[first_command..], &matrix, opacity);
+    // Layers inside of a precomposition keep their own blend mode
+    let blending = layer_blending(layer.blend_mode);
+    if blending.is_fixed_function() {
+        for command in &mut commands[first_command..] {
+            if command.blending == Blending::Normal {
+                command.blending = blending;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Blend modes without a [Blending] fall back to normal alpha blending for now
+fn layer_blending(blend_mode: Option<BlendMode>) -> Blending {
+    match blend_mode {
+        Some(BlendMode::Multiply) => Blending::Multiply,
+        Some(BlendMode::Screen) => Blending::Screen,
+        Some(BlendMode::Add) => Blending::Add,
+        Some(BlendMode::Overlay) => Blending::Overlay,
+        Some(BlendMode::Darken) => Blending::Darken,
+        Some(BlendMode::Lighten) => Blending::Lighten,
+        Some(BlendMode::Difference) => Blending::Difference,
+        _ => Blending::Normal,
+    }
+}
+
+/// The [RenderOperation] which composites a layer through its track matte, if it has one
+fn matte_operation(matte_mode: Option<MatteMode>) -> Option<RenderOperation> {
+    let (luma, inverted) = match matte_mode? {
+        MatteMode::Normal => return None,
+        MatteMode::Alpha => (false, false),
+        MatteMode::InvertedAlpha => (false, true),
+        MatteMode::Luma => (true, false),
+        MatteMode::InvertedLuma => (true, true),
+    };
+    Some(RenderOperation::RestoreMatteContext { luma, inverted })
+}
+
+/// Finds the track matte of the layer at `index`, which is either referenced explicitly or the layer above it.
+fn matte_layer(layers: &[Layer], index: usize) -> Result<&Layer, Error> {
+    match layers[index].matte_parent {
+        Some(matte_parent) => layers
+            .iter()
+            .find(|layer| layer.index == Some(matte_parent))
+            .ok_or(Error::MissingMatte(Some(matte_parent))),
+        None => index
+            .checked_sub(1)
+            .map(|above| &layers[above])
+            .ok_or(Error::MissingMatte(None)),
+    }
+}
+
+/// Evaluates the layers of a composition at the given frame and appends their [DrawCommand]s topmost first.
+///
+/// Layers with a track matte are rendered into an offscreen matte context,
+/// the [RenderOperation]s which delimit it are pushed in reverse as well.
+/// Track mattes themselves are rendered even if they are hidden, but only as matte.
+///
+/// Layers with a [Blending] which is not fixed-function are rendered into an offscreen blend context,
+/// whose backdrop consists of all layers beneath them in the same composition.
+///
+/// `precompositions` are the ids of the enclosing precompositions, which must not be entered again.
+fn render_layers<'a>(
+    model: &'a Model,
+    layers: &'a [Layer],
+    frame: f32,
+    precompositions: &mut Vec<&'a str>,
+    commands: &mut Vec<DrawCommand>,
+) -> Result<(), Error> {
+    let mut blend_contexts = 0;
+    for (index, layer) in layers.iter().enumerate() {
+        // Hidden layers are only used as parents
+        if layer.hidden || layer.is_matte {
+            continue;
+        }
+        let blending = layer_blending(layer.blend_mode);
+        if !blending.is_fixed_function() {
+            commands.push(state_command(
+                vec![],
+                RenderOperation::RestoreBlendContext(blending),
+            ));
+        }
+        match matte_operation(layer.matte_mode) {
+            Some(operation) => {
+                let matte = matte_layer(layers, index)?;
+                commands.push(state_command(vec![], operation));
+                render_layer(model, layers, layer, frame, precompositions, commands)?;
+                commands.push(state_command(vec![], RenderOperation::ApplyMatteContext));
+                render_layer(model, layers, matte, frame, precompositions, commands)?;
+                commands.push(state_command(vec![], RenderOperation::SaveMatteContext));
+            }
+            None => render_layer(model, layers, layer, frame, precompositions, commands)?,
+        }
+        if !blending.is_fixed_function() {
+            commands.push(state_command(vec![], RenderOperation::ApplyBlendContext));
+            blend_contexts += 1;
+        }
+    }
+    // The backdrops extend to the bottom of the composition
+    for _ in 0..blend_contexts {
+        commands.push(state_command(vec![], RenderOperation::SaveBlendContext));
+    }
+    Ok(())
+}
+
+/// Evaluates the [Model] at the given frame and returns the [DrawCommand]s in drawing order.
+///
+/// Nothing is drawn outside of the in and out point of the composition.
+pub fn render_lottie(model: &Model, frame: f32) -> Result<Vec<DrawCommand>, Error> {
+    let mut commands = vec![];
+    if frame < model.start_frame || frame >= model.end_frame {
+        return Ok(commands);
+    }
+    render_layers(model, &model.layers, frame, &mut vec![], &mut commands)?;
+    // Layers and styles are listed topmost first
+    commands.reverse();
+    Ok(flatten_clips(commands))
+}
+
+/// Resolves nested clips, so that the renderer only has to handle one clip area at a time.
+///
+/// Every [RenderOperation::Clip] is intersected with the enclosing clip areas,
+/// and every [RenderOperation::UnClip] inside of another clip restores the enclosing clip area.
+/// Each offscreen target has a clip area of its own, so the current one is set again
+/// after every [RenderOperation] which switches to the target of a matte or blend context.
+fn flatten_clips(commands: Vec<DrawCommand>) -> Vec<DrawCommand> {
+    let mut areas: Vec<Vec<Path>> = vec![];
+    let mut result = Vec::with_capacity(commands.len());
+    for mut command in commands {
+        match command.operation {
+            RenderOperation::Clip => {
+                if let Some(outer) = areas.last() {
+                    command.paths =
+                        combine_areas(&[outer, &command.paths], BooleanOperation::Intersection);
+                }
+                areas.push(command.paths.clone());
+                result.push(command);
+            }
+            RenderOperation::UnClip => {
+                areas.pop();
+                result.push(match areas.last() {
+                    Some(outer) => state_command(outer.clone(), RenderOperation::Clip),
+                    None => command,
+                });
+            }
+            RenderOperation::SaveMatteContext
+            | RenderOperation::ApplyMatteContext
+            | RenderOperation::SaveBlendContext
+            | RenderOperation::ApplyBlendContext => {
+                result.push(command);
+                if let Some(area) = areas.last() {
+                    result.push(state_command(area.clone(), RenderOperation::Clip));
+                }
             }
-            _ => unimplemented!(),
+            _ => result.push(command),
         }
     }
+    result
 }
@@ -0,0 +1,205 @@
+//! Conversion of the Lottie shape geometry into [Path]s
+
+use crate::{
+    model::{Bezier, Ellipse, PolyStar, PolyStarType, Rectangle, ShapeDirection, Vector2D},
+    path_rendering::path::{
+        IntegralCubicCurveSegment, IntegralQuadraticCurveSegment, LineSegment, Path,
+    },
+};
+
+/// Tolerance for detecting degenerated tangents and curves which are actually quadratic
+const TANGENT_EPSILON: f32 = 0.0001;
+
+fn approx_eq(a: [f32; 2], b: [f32; 2]) -> bool {
+    (a[0] - b[0]).abs() <= TANGENT_EPSILON && (a[1] - b[1]).abs() <= TANGENT_EPSILON
+}
+
+/// Appends the segment from the current end of the [Path] to `to`.
+///
+/// `out_tangent` and `in_tangent` are the absolute control points of the cubic bezier.
+/// Lines and quadratic curves are detected, so that cheaper segment types can be used.
+fn push_segment(path: &mut Path, out_tangent: [f32; 2], in_tangent: [f32; 2], to: [f32; 2]) {
+    let from = path.get_end();
+    if approx_eq(out_tangent, from) && approx_eq(in_tangent, to) {
+        path.push_line(LineSegment {
+            control_points: [to.into()],
+        });
+        return;
+    }
+    // A degree elevated quadratic curve has its control point at (3 * c1 - p0) / 2 = (3 * c2 - p3) / 2
+    let quadratic_from_start = [
+        (3.0 * out_tangent[0] - from[0]) * 0.5,
+        (3.0 * out_tangent[1] - from[1]) * 0.5,
+    ];
+    let quadratic_from_end = [
+        (3.0 * in_tangent[0] - to[0]) * 0.5,
+        (3.0 * in_tangent[1] - to[1]) * 0.5,
+    ];
+    if approx_eq(quadratic_from_start, quadratic_from_end) {
+        path.push_integral_quadratic_curve(IntegralQuadraticCurveSegment {
+            control_points: [quadratic_from_start.into(), to.into()],
+        });
+    } else {
+        path.push_integral_cubic_curve(IntegralCubicCurveSegment {
+            control_points: [out_tangent.into(), in_tangent.into(), to.into()],
+        });
+    }
+}
+
+/// Converts a Lottie [Bezier] into a [Path].
+///
+/// The tangents of a [Bezier] are relative to their vertex.
+/// Closed [Bezier]s end exactly at their start (see [Path::is_closed]),
+/// so that trimming and stroking can tell them apart from open ones.
+/// [ShapeDirection::CounterClockwise] reverses the [Path], which flips its winding.
+pub fn path_from_bezier(bezier: &Bezier, direction: ShapeDirection) -> Option<Path> {
+    let first = bezier.verticies.first()?;
+    let mut path = Path {
+        start: [first.0.x, first.0.y].into(),
+        ..Path::default()
+    };
+    let absolute = |tangents: &[Vector2D], index: usize| {
+        let vertex = &bezier.verticies[index];
+        let tangent = tangents.get(index).cloned().unwrap_or_default();
+        [vertex.0.x + tangent.0.x, vertex.0.y + tangent.0.y]
+    };
+    for index in 1..bezier.verticies.len() {
+        let vertex = &bezier.verticies[index];
+        push_segment(
+            &mut path,
+            absolute(&bezier.out_tangent, index - 1),
+            absolute(&bezier.in_tangent, index),
+            [vertex.0.x, vertex.0.y],
+        );
+    }
+    if bezier.closed && bezier.verticies.len() > 1 {
+        let last = bezier.verticies.len() - 1;
+        let out_tangent = absolute(&bezier.out_tangent, last);
+        let in_tangent = absolute(&bezier.in_tangent, 0);
+        let to = [first.0.x, first.0.y];
+        let from = path.get_end();
+        // Some exporters repeat the first vertex at the end
+        if !(approx_eq(from, to) && approx_eq(out_tangent, from) && approx_eq(in_tangent, to)) {
+            push_segment(&mut path, out_tangent, in_tangent, to);
+        }
+    }
+    if direction == ShapeDirection::CounterClockwise {
+        path.reverse();
+    }
+    Some(path)
+}
+
+/// Converts all sub paths of a Lottie path shape into [Path]s.
+pub fn paths_from_beziers(beziers: &[Bezier], direction: ShapeDirection) -> Vec<Path> {
+    beziers
+        .iter()
+        .filter_map(|bezier| path_from_bezier(bezier, direction))
+        .collect()
+}
+
+/// The path helpers run counterclockwise in the y-down coordinate system of Lottie,
+/// while Lottie shapes start clockwise by default.
+fn orient(mut path: Path, direction: ShapeDirection) -> Path {
+    path.close();
+    if direction == ShapeDirection::Clockwise {
+        path.reverse();
+    }
+    path
+}
+
+/// Generates the [Path] of a [Rectangle] at the given frame.
+///
+/// The corner radius is clamped to half of the shorter side like in lottie-web.
+pub fn rectangle_path(rectangle: &Rectangle, frame: f32) -> Path {
+    let position = rectangle.position.value_at(frame);
+    let size = rectangle.size.value_at(frame);
+    let center = [position.0.x, position.0.y];
+    let half_extent = [size.0.x.abs() * 0.5, size.0.y.abs() * 0.5];
+    let radius = rectangle
+        .radius
+        .value_at(frame)
+        .min(half_extent[0])
+        .min(half_extent[1]);
+    let path = if radius > 0.0 {
+        Path::from_rounded_rect(center, half_extent, radius)
+    } else {
+        Path::from_rect(center, half_extent)
+    };
+    orient(path, rectangle.direction)
+}
+
+/// Generates the [Path] of an [Ellipse] at the given frame.
+pub fn ellipse_path(ellipse: &Ellipse, frame: f32) -> Path {
+    let position = ellipse.position.value_at(frame);
+    let size = ellipse.size.value_at(frame);
+    let path = Path::from_ellipse(
+        [position.0.x, position.0.y],
+        [size.0.x.abs() * 0.5, size.0.y.abs() * 0.5],
+    );
+    orient(path, ellipse.direction)
+}
+
+/// Generates the [Path] of a [PolyStar] at the given frame.
+///
+/// Like in lottie-web the first vertex points up (before rotation) and the vertices run clockwise.
+/// Roundness turns the corners into curves whose tangents are perpendicular to the radius.
+pub fn polystar_path(star: &PolyStar, frame: f32) -> Path {
+    let position = star.position.value_at(frame);
+    let center = [position.0.x, position.0.y];
+    let points = (star.points.value_at(frame).floor() as usize).max(3);
+    let rotation = (star.rotation.value_at(frame) - 90.0).to_radians();
+    let outer_radius = star.outer_radius.value_at(frame);
+    let outer_roundness = star.outer_roundness.value_at(frame) / 100.0;
+    let (inner_radius, inner_roundness) = match star.star_type {
+        PolyStarType::Star => (
+            star.inner_radius
+                .as_ref()
+                .map(|inner_radius| inner_radius.value_at(frame))
+                .unwrap_or_default(),
+            star.inner_roundness
+                .as_ref()
+                .map(|inner_roundness| inner_roundness.value_at(frame) / 100.0)
+                .unwrap_or_default(),
+        ),
+        PolyStarType::Polygon => (outer_radius, outer_roundness),
+    };
+    if star.star_type == PolyStarType::Polygon && outer_roundness == 0.0 {
+        let mut path = Path::from_regular_polygon(center, outer_radius, rotation, points);
+        path.close();
+        if star.direction == ShapeDirection::CounterClockwise {
+            path.reverse();
+        }
+        return path;
+    }
+    // A polygon is a star whose inner vertices coincide with the outer ones, so only every second vertex is emitted
+    let (vertex_count, step) = match star.star_type {
+        PolyStarType::Star => (points * 2, 1),
+        PolyStarType::Polygon => (points * 2, 2),
+    };
+    let angle_step = std::f32::consts::PI * 2.0 / vertex_count as f32;
+    let mut bezier = Bezier {
+        closed: true,
+        ..Bezier::default()
+    };
+    for i in (0..vertex_count).step_by(step) {
+        let (radius, roundness) = if i % 2 == 0 {
+            (outer_radius, outer_roundness)
+        } else {
+            (inner_radius, inner_roundness)
+        };
+        let angle = rotation + angle_step * i as f32;
+        let (sin, cos) = angle.sin_cos();
+        // A quarter of the perimeter segment, scaled by the roundness
+        let tangent_length = std::f32::consts::PI * 2.0 * radius / (points * 4) as f32 * roundness;
+        let tangent = Vector2D::new(-sin * tangent_length, cos * tangent_length);
+        bezier.verticies.push(Vector2D::new(
+            center[0] + radius * cos,
+            center[1] + radius * sin,
+        ));
+        bezier
+            .in_tangent
+            .push(Vector2D::new(-tangent.0.x, -tangent.0.y));
+        bezier.out_tangent.push(tangent);
+    }
+    path_from_bezier(&bezier, star.direction).unwrap()
+}
@@ -0,0 +1,62 @@
+//! Loading the encoded data of image assets
+
+use super::Error;
+use crate::model::Media;
+use std::fs;
+
+/// Decodes standard base64 with optional padding, ignoring whitespace
+fn decode_base64(encoded: &str) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(encoded.len() / 4 * 3);
+    let mut accumulator = 0u32;
+    let mut bits = 0;
+    for byte in encoded.bytes() {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            _ if byte.is_ascii_whitespace() => continue,
+            _ => return None,
+        };
+        accumulator = accumulator << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((accumulator >> bits) as u8);
+        }
+    }
+    Some(decoded)
+}
+
+/// Extracts the data of a `data:` URI, which is either base64 or percent encoded
+fn decode_data_uri(uri: &str) -> Option<Vec<u8>> {
+    let (header, data) = uri.strip_prefix("data:")?.split_once(',')?;
+    if header.ends_with(";base64") {
+        return decode_base64(data);
+    }
+    let mut decoded = Vec::with_capacity(data.len());
+    let mut bytes = data.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte == b'%' {
+            let digits = [bytes.next()?, bytes.next()?];
+            decoded.push(u8::from_str_radix(std::str::from_utf8(&digits).ok()?, 16).ok()?);
+        } else {
+            decoded.push(byte);
+        }
+    }
+    Some(decoded)
+}
+
+/// Returns the encoded image of a [Media] asset.
+///
+/// Embedded images are data URIs, others are files at `pwd` + `filename` relative to the working directory.
+pub fn media_data(media: &Media) -> Result<Vec<u8>, Error> {
+    if media.embedded || media.filename.starts_with("data:") {
+        return decode_data_uri(&media.filename)
+            .ok_or_else(|| Error::InvalidImage(media.id.clone()));
+    }
+    fs::read(format!("{}{}", media.pwd, media.filename))
+        .map_err(|_| Error::InvalidImage(media.id.clone()))
+}
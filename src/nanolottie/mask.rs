@@ -0,0 +1,69 @@
+//! Layer masks which limit the area in which the content of a layer is visible
+
+use super::{geometry::paths_from_beziers, modifiers::expand_paths};
+use crate::{
+    model::{Mask, MaskMode, ShapeDirection},
+    path_rendering::{
+        boolean::{combine_areas, BooleanOperation},
+        path::Path,
+    },
+};
+
+/// Half the side length of the square which stands in for the infinite plane
+const UNBOUNDED: f32 = 16384.0;
+
+fn unbounded() -> Vec<Path> {
+    vec![Path::from_rect([0.0, 0.0], [UNBOUNDED, UNBOUNDED])]
+}
+
+/// The area enclosed by a single [Mask] at the given frame
+fn mask_paths(mask: &Mask, frame: f32) -> Vec<Path> {
+    let mut paths = paths_from_beziers(&mask.points.value_at(frame), ShapeDirection::default());
+    // Masks are always closed, even if their path is not
+    for path in &mut paths {
+        path.close();
+    }
+    if let Some(expand) = &mask.expand {
+        paths = expand_paths(paths, expand.value_at(frame));
+    }
+    if mask.inverted {
+        paths = combine_areas(&[&unbounded(), &paths], BooleanOperation::Difference);
+    }
+    paths
+}
+
+/// Combines the [Mask]s of a layer in list order at the given frame.
+///
+/// Returns the visible area in the coordinate system of the layer and the opacity of the content inside of it,
+/// or [None] if no mask takes part, in which case the layer is not masked at all.
+/// A first mask which removes area (subtract, intersect or darken) starts from the whole plane.
+///
+/// Clipping is binary, so the opacities of all but the subtracting masks are multiplied
+/// and applied to the whole content, while partially transparent subtracting masks subtract fully.
+pub fn mask_area(masks: &[Mask], frame: f32) -> Option<(Vec<Path>, f32)> {
+    let mut area: Option<Vec<Path>> = None;
+    let mut opacity = 1.0;
+    for mask in masks {
+        let (operation, removes_area) = match mask.mode {
+            MaskMode::None => continue,
+            MaskMode::Add | MaskMode::Lighten => (BooleanOperation::Union, false),
+            MaskMode::Subtract => (BooleanOperation::Difference, true),
+            MaskMode::Intersect | MaskMode::Darken => (BooleanOperation::Intersection, true),
+            MaskMode::Difference => (BooleanOperation::SymmetricDifference, false),
+        };
+        let mask_opacity = (mask.opacity.value_at(frame) / 100.0).clamp(0.0, 1.0);
+        let current = area.unwrap_or_else(|| if removes_area { unbounded() } else { vec![] });
+        // Transparent masks enclose no area
+        if mask_opacity == 0.0 {
+            let nothing_left = operation == BooleanOperation::Intersection;
+            area = Some(if nothing_left { vec![] } else { current });
+            continue;
+        }
+        if operation != BooleanOperation::Difference {
+            opacity *= mask_opacity;
+        }
+        let paths = mask_paths(mask, frame);
+        area = Some(combine_areas(&[&current, &paths], operation));
+    }
+    area.map(|area| (area, opacity))
+}
@@ -0,0 +1,612 @@
+//! Shape modifiers which alter the geometry accumulated before them in a group
+
+use crate::{
+    model::{LineJoin, Merge, MergeMode, OffsetPath, Trim, TrimMultipleShape, ZigZag},
+    path_rendering::{
+        boolean::{combine_areas, BooleanOperation},
+        path::{Path, Segment},
+    },
+};
+
+/// Number of pieces a cubic curve is split into before offsetting its control polygon
+const OFFSET_SUBDIVISIONS: usize = 4;
+
+/// Tolerance for detecting degenerated segments and joins which need no geometry
+const EPSILON: f32 = 0.0001;
+
+fn sub(a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+    [a[0] - b[0], a[1] - b[1]]
+}
+
+fn add_scaled(a: [f32; 2], b: [f32; 2], scale: f32) -> [f32; 2] {
+    [a[0] + b[0] * scale, a[1] + b[1] * scale]
+}
+
+fn cross(a: [f32; 2], b: [f32; 2]) -> f32 {
+    a[0] * b[1] - a[1] * b[0]
+}
+
+fn length(a: [f32; 2]) -> f32 {
+    (a[0] * a[0] + a[1] * a[1]).sqrt()
+}
+
+fn normalize(a: [f32; 2]) -> Option<[f32; 2]> {
+    let length = length(a);
+    (length > EPSILON).then(|| [a[0] / length, a[1] / length])
+}
+
+/// The normal which points outwards for clockwise paths in the y-down coordinate system of Lottie
+fn normal(tangent: [f32; 2]) -> [f32; 2] {
+    [tangent[1], -tangent[0]]
+}
+
+/// Intersects the lines through `a` and `b` along the directions `da` and `db`.
+///
+/// Returns the parameter along `da`, or [None] if the lines are parallel.
+fn line_intersection(a: [f32; 2], da: [f32; 2], b: [f32; 2], db: [f32; 2]) -> Option<f32> {
+    let denominator = cross(da, db);
+    (denominator.abs() > EPSILON).then(|| cross(sub(b, a), db) / denominator)
+}
+
+/// Cuts the window [begin, end] (as arc lengths, `end` may exceed `length` to wrap around) out of a [Path].
+fn trim_path(path: &Path, begin: f32, end: f32, length: f32) -> Vec<Path> {
+    if end <= length {
+        return path.trim(begin, end).into_iter().collect();
+    }
+    let head = path.trim(begin, length);
+    let tail = path.trim(0.0, end - length);
+    match (head, tail) {
+        // The window wraps over the start of a closed path, so both parts form one contiguous path
+        (Some(mut head), Some(tail)) if path.is_closed() => {
+            for segment in tail.segments() {
+                head.push_segment(&segment);
+            }
+            vec![head]
+        }
+        (head, tail) => head.into_iter().chain(tail).collect(),
+    }
+}
+
+/// Applies a [Trim] to all areas accumulated before it, each area keeps its trimmed paths.
+///
+/// Start and end are percentages of the arc length and the offset (in degrees) rotates the window,
+/// wrapping around the end like in lottie-web.
+pub fn trim_paths(areas: Vec<Vec<Path>>, trim: &Trim, frame: f32) -> Vec<Vec<Path>> {
+    let mut begin = trim.start.value_at(frame) / 100.0;
+    let mut end = trim.end.value_at(frame) / 100.0;
+    if begin > end {
+        std::mem::swap(&mut begin, &mut end);
+    }
+    let window = (end - begin).min(1.0);
+    if window >= 1.0 {
+        return areas;
+    }
+    if window <= 0.0 {
+        return vec![Vec::new(); areas.len()];
+    }
+    let begin = (begin + trim.offset.value_at(frame) / 360.0).rem_euclid(1.0);
+    let end = begin + window;
+    match trim.multiple_shape {
+        TrimMultipleShape::Simultaneously => areas
+            .iter()
+            .map(|paths| {
+                paths
+                    .iter()
+                    .flat_map(|path| {
+                        let length = path.length();
+                        trim_path(path, begin * length, end * length, length)
+                    })
+                    .collect()
+            })
+            .collect(),
+        TrimMultipleShape::Individually => {
+            let total_length: f32 = areas.iter().flatten().map(Path::length).sum();
+            // The window on the concatenation of all paths, split in two if it wraps around
+            let mut windows = vec![(begin * total_length, end.min(1.0) * total_length)];
+            if end > 1.0 {
+                windows.push((0.0, (end - 1.0) * total_length));
+            }
+            let mut path_begin = 0.0;
+            areas
+                .iter()
+                .map(|paths| {
+                    let mut result = Vec::new();
+                    for path in paths {
+                        let length = path.length();
+                        for (window_begin, window_end) in &windows {
+                            let local_begin = (window_begin - path_begin).max(0.0);
+                            let local_end = (window_end - path_begin).min(length);
+                            if local_end > local_begin {
+                                result.extend(path.trim(local_begin, local_end));
+                            }
+                        }
+                        path_begin += length;
+                    }
+                    result
+                })
+                .collect()
+        }
+    }
+}
+
+/// Offsets a [Segment] by moving the edges of its control polygon along their normals (Tiller-Hanson).
+///
+/// This is exact for lines and circular arcs and a close approximation for short curves.
+fn offset_segment(segment: &Segment, distance: f32) -> Segment {
+    let control_points = segment.control_points();
+    let last = control_points.len() - 1;
+    // Degenerated edges of the control polygon fall back to the tangents at the ends
+    let mut directions = Vec::with_capacity(last);
+    for i in 0..last {
+        let direction = normalize(sub(control_points[i + 1], control_points[i]))
+            .or_else(|| directions.last().copied())
+            .unwrap_or_else(|| segment.tangent(0.0));
+        directions.push(direction);
+    }
+    let mut offset_points = Vec::with_capacity(control_points.len());
+    offset_points.push(add_scaled(
+        control_points[0],
+        normal(directions[0]),
+        distance,
+    ));
+    for i in 1..last {
+        let previous = add_scaled(control_points[i - 1], normal(directions[i - 1]), distance);
+        let next = add_scaled(control_points[i], normal(directions[i]), distance);
+        offset_points.push(
+            match line_intersection(previous, directions[i - 1], next, directions[i]) {
+                Some(t) => add_scaled(previous, directions[i - 1], t),
+                None => next,
+            },
+        );
+    }
+    offset_points.push(add_scaled(
+        control_points[last],
+        normal(directions[last - 1]),
+        distance,
+    ));
+    segment.with_control_points(&offset_points)
+}
+
+/// Appends the geometry which connects two offset [Segment]s meeting at `vertex`.
+///
+/// `from` and `to` are the end points of the offset [Segment]s with their tangents.
+fn push_join(
+    segments: &mut Vec<Segment>,
+    vertex: [f32; 2],
+    (from, from_tangent): ([f32; 2], [f32; 2]),
+    (to, to_tangent): ([f32; 2], [f32; 2]),
+    line_join: LineJoin,
+    miter_limit: f32,
+) {
+    if length(sub(to, from)) <= EPSILON {
+        return;
+    }
+    let crossing = line_intersection(from, from_tangent, to, to_tangent)
+        .filter(|t| *t > 0.0)
+        .map(|t| add_scaled(from, from_tangent, t));
+    // Only the outer side of a corner opens a gap, the inner side overlaps
+    match (crossing, line_join) {
+        (Some(crossing), LineJoin::Miter)
+            if length(sub(crossing, vertex)) <= miter_limit * length(sub(from, vertex)) =>
+        {
+            segments.push(Segment::Line([from, crossing]));
+            segments.push(Segment::Line([crossing, to]));
+        }
+        (Some(crossing), LineJoin::Round) => {
+            // A circular arc is a rational quadratic curve whose weight is the cosine of half its angle
+            let half_angle_cosine = ((from_tangent[0] * to_tangent[0]
+                + from_tangent[1] * to_tangent[1])
+                .clamp(-1.0, 1.0)
+                .acos()
+                * 0.5)
+                .cos();
+            segments.push(Segment::RationalQuadraticCurve(
+                half_angle_cosine,
+                [from, crossing, to],
+            ));
+        }
+        _ => segments.push(Segment::Line([from, to])),
+    }
+}
+
+fn offset_path(path: &Path, distance: f32, line_join: LineJoin, miter_limit: f32) -> Option<Path> {
+    let mut pieces = Vec::new();
+    for segment in path.segments() {
+        if segment.length() <= EPSILON {
+            continue;
+        }
+        match segment {
+            Segment::IntegralCubicCurve(_) | Segment::RationalCubicCurve(_, _) => {
+                for i in 0..OFFSET_SUBDIVISIONS {
+                    pieces.push(segment.sub_segment(
+                        i as f32 / OFFSET_SUBDIVISIONS as f32,
+                        (i + 1) as f32 / OFFSET_SUBDIVISIONS as f32,
+                    ));
+                }
+            }
+            _ => pieces.push(segment),
+        }
+    }
+    let closed = path.is_closed();
+    let mut segments = Vec::with_capacity(pieces.len() * 2);
+    for (index, piece) in pieces.iter().enumerate() {
+        let offset = offset_segment(piece, distance);
+        if let Some(previous) = segments.last().copied() {
+            push_join(
+                &mut segments,
+                piece.start(),
+                (previous.end(), pieces[index - 1].tangent(1.0)),
+                (offset.start(), piece.tangent(0.0)),
+                line_join,
+                miter_limit,
+            );
+        }
+        segments.push(offset);
+    }
+    if closed {
+        let (first, last) = (pieces.first()?, pieces.last()?);
+        let (start, end) = (segments.first()?.start(), segments.last()?.end());
+        push_join(
+            &mut segments,
+            first.start(),
+            (end, last.tangent(1.0)),
+            (start, first.tangent(0.0)),
+            line_join,
+            miter_limit,
+        );
+    }
+    Path::from_segments(&segments)
+}
+
+/// Applies an [OffsetPath] to all paths accumulated before it.
+///
+/// Positive amounts expand clockwise paths, the gaps opening at corners are filled according to the [LineJoin].
+pub fn offset_paths(paths: Vec<Path>, offset: &OffsetPath, frame: f32) -> Vec<Path> {
+    let amount = offset.amount.value_at(frame);
+    if amount == 0.0 {
+        return paths;
+    }
+    let miter_limit = offset.miter_limit.value_at(frame);
+    paths
+        .iter()
+        .filter_map(|path| offset_path(path, amount, offset.line_join, miter_limit))
+        .collect()
+}
+
+/// Twice the signed area enclosed by a closed [Path], positive if it runs clockwise in the y-down coordinate system
+fn signed_area(path: &Path) -> f32 {
+    let mut polygon = Vec::new();
+    for segment in path.segments() {
+        for i in 1..=OFFSET_SUBDIVISIONS {
+            polygon.push(segment.point(i as f32 / OFFSET_SUBDIVISIONS as f32));
+        }
+    }
+    (0..polygon.len())
+        .map(|i| cross(polygon[i], polygon[(i + 1) % polygon.len()]))
+        .sum()
+}
+
+/// Grows the areas enclosed by closed [Path]s by `amount`, or shrinks them if it is negative.
+///
+/// Unlike [offset_paths] this does not depend on the orientation of the [Path]s, corners are rounded.
+pub fn expand_paths(paths: Vec<Path>, amount: f32) -> Vec<Path> {
+    if amount == 0.0 {
+        return paths;
+    }
+    paths
+        .into_iter()
+        .filter_map(|mut path| {
+            if signed_area(&path) < 0.0 {
+                path.reverse();
+            }
+            offset_path(&path, amount, LineJoin::Round, 0.0)
+        })
+        .collect()
+}
+
+fn zig_zag_path(path: &Path, frequency: usize, amplitude: f32, smooth: bool) -> Option<Path> {
+    let segments = path
+        .segments()
+        .into_iter()
+        .filter(|segment| segment.length() > EPSILON)
+        .collect::<Vec<_>>();
+    let closed = path.is_closed();
+    // Ridges alternate between both sides of the path, every vertex of the path is a ridge too
+    let mut ridges = Vec::new();
+    let mut side = 1.0;
+    for (index, segment) in segments.iter().enumerate() {
+        let segment_length = segment.length();
+        let previous = if index > 0 {
+            segments.get(index - 1)
+        } else if closed {
+            segments.last()
+        } else {
+            None
+        };
+        for i in 0..=frequency {
+            let t = segment.parameter_at_length(segment_length * i as f32 / (frequency + 1) as f32);
+            let mut tangent = segment.tangent(t);
+            // The ridge at a vertex of the path points along the bisector of its corner
+            if let (0, Some(previous)) = (i, previous) {
+                let previous_tangent = previous.tangent(1.0);
+                tangent = normalize([
+                    tangent[0] + previous_tangent[0],
+                    tangent[1] + previous_tangent[1],
+                ])
+                .unwrap_or(tangent);
+            }
+            ridges.push((
+                add_scaled(segment.point(t), normal(tangent), amplitude * side),
+                tangent,
+                segment_length / (frequency + 1) as f32,
+            ));
+            side = -side;
+        }
+    }
+    if !closed {
+        let last = segments.last()?;
+        let tangent = last.tangent(1.0);
+        ridges.push((
+            add_scaled(last.end(), normal(tangent), amplitude * side),
+            tangent,
+            0.0,
+        ));
+    }
+    let mut result = Path {
+        start: ridges.first()?.0.into(),
+        ..Path::default()
+    };
+    let connections = if closed {
+        ridges.len()
+    } else {
+        ridges.len() - 1
+    };
+    for i in 0..connections {
+        let (from, from_tangent, spacing) = ridges[i];
+        let (to, to_tangent, _) = ridges[(i + 1) % ridges.len()];
+        result.push_segment(&if smooth {
+            let handle = spacing / 3.0;
+            Segment::IntegralCubicCurve([
+                from,
+                add_scaled(from, from_tangent, handle),
+                add_scaled(to, to_tangent, -handle),
+                to,
+            ])
+        } else {
+            Segment::Line([from, to])
+        });
+    }
+    Some(result)
+}
+
+/// Applies a [ZigZag] to all paths accumulated before it.
+///
+/// Each segment gets `frequency` ridges spaced evenly along its arc length.
+pub fn zig_zag_paths(paths: Vec<Path>, zig_zag: &ZigZag, frame: f32) -> Vec<Path> {
+    let frequency = zig_zag.frequency.value_at(frame).max(0.0) as usize;
+    let amplitude = zig_zag.amplitude.value_at(frame);
+    let smooth = zig_zag.point_type.value_at(frame).round() as i32 == 2;
+    if amplitude == 0.0 {
+        return paths;
+    }
+    paths
+        .iter()
+        .filter_map(|path| zig_zag_path(path, frequency, amplitude, smooth))
+        .collect()
+}
+
+/// Applies a [Merge] to all areas accumulated before it and returns the combined area.
+///
+/// Each area (the paths of one shape) is an operand, so that the holes of compound shapes stay holes.
+/// The first area is the base which the others are combined with.
+pub fn merge_paths(areas: Vec<Vec<Path>>, merge: &Merge) -> Vec<Path> {
+    let operation = match merge.mode {
+        MergeMode::Normal => return areas.concat(),
+        MergeMode::Add => BooleanOperation::Union,
+        MergeMode::Subtract => BooleanOperation::Difference,
+        MergeMode::Intersect => BooleanOperation::Intersection,
+        MergeMode::Exclude => BooleanOperation::SymmetricDifference,
+    };
+    let areas = areas.iter().map(Vec::as_slice).collect::<Vec<_>>();
+    combine_areas(&areas, operation)
+}
+
+/// Length of the handles of a cubic curve approximating a quarter circle, relative to its radius
+const ROUND_CORNER_HANDLE: f32 = 0.5519;
+
+/// Number of pieces every segment is split into before it is twisted
+const TWIST_SUBDIVISIONS: usize = 4;
+
+/// Converts a [Segment] into the control points of a cubic curve.
+///
+/// Lines and integral quadratic curves are degree elevated exactly, rational curves are approximated.
+fn cubic_control_points(segment: &Segment) -> [[f32; 2]; 4] {
+    match *segment {
+        Segment::Line([a, b]) => [a, lerp(a, b, 1.0 / 3.0), lerp(a, b, 2.0 / 3.0), b],
+        Segment::IntegralQuadraticCurve([a, b, c]) => {
+            [a, lerp(a, b, 2.0 / 3.0), lerp(c, b, 2.0 / 3.0), c]
+        }
+        Segment::RationalQuadraticCurve(weight, [a, b, c]) => {
+            // Matches the usual cubic approximation of circular arcs
+            let handle = 4.0 / 3.0 * weight / (1.0 + weight);
+            [a, lerp(a, b, handle), lerp(c, b, handle), c]
+        }
+        Segment::IntegralCubicCurve(control_points)
+        | Segment::RationalCubicCurve(_, control_points) => control_points,
+    }
+}
+
+fn lerp(a: [f32; 2], b: [f32; 2], t: f32) -> [f32; 2] {
+    [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t]
+}
+
+fn rounded_corners_path(path: &Path, radius: f32) -> Option<Path> {
+    let segments = path
+        .segments()
+        .into_iter()
+        .filter(|segment| segment.length() > EPSILON)
+        .collect::<Vec<_>>();
+    let closed = path.is_closed();
+    // The corner at the end of every segment, only corners between two lines are rounded
+    let corners = (0..segments.len())
+        .map(|index| {
+            let next = if index + 1 < segments.len() {
+                &segments[index + 1]
+            } else if closed {
+                &segments[0]
+            } else {
+                return 0.0;
+            };
+            match (&segments[index], next) {
+                (Segment::Line([a, b]), Segment::Line([_, c]))
+                    if cross(sub(*b, *a), sub(*c, *b)).abs() > EPSILON =>
+                {
+                    radius
+                        .min(length(sub(*b, *a)) * 0.5)
+                        .min(length(sub(*c, *b)) * 0.5)
+                }
+                _ => 0.0,
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut result = Vec::with_capacity(segments.len() * 2);
+    for (index, segment) in segments.iter().enumerate() {
+        let previous_corner = if index > 0 {
+            corners[index - 1]
+        } else if closed {
+            *corners.last()?
+        } else {
+            0.0
+        };
+        let corner = corners[index];
+        let mut segment = *segment;
+        if let Segment::Line([a, b]) = segment {
+            let direction = normalize(sub(b, a))?;
+            segment = Segment::Line([
+                add_scaled(a, direction, previous_corner),
+                add_scaled(b, direction, -corner),
+            ]);
+        }
+        if segment.length() > EPSILON {
+            result.push(segment);
+        }
+        if corner > 0.0 {
+            let next = &segments[(index + 1) % segments.len()];
+            let vertex = segments[index].end();
+            let from = segment.end();
+            let to = add_scaled(vertex, next.tangent(0.0), corner);
+            result.push(Segment::IntegralCubicCurve([
+                from,
+                lerp(from, vertex, ROUND_CORNER_HANDLE),
+                lerp(to, vertex, ROUND_CORNER_HANDLE),
+                to,
+            ]));
+        }
+    }
+    Path::from_segments(&result)
+}
+
+/// Applies a RoundedCorners modifier to all paths accumulated before it,
+/// which the styles of its group paint even if they are listed before it.
+///
+/// Only sharp corners between two straight segments are rounded,
+/// the radius is limited to half the length of the shorter one.
+pub fn rounded_corners_paths(paths: Vec<Path>, radius: f32) -> Vec<Path> {
+    if radius <= 0.0 {
+        return paths;
+    }
+    paths
+        .iter()
+        .filter_map(|path| rounded_corners_path(path, radius))
+        .collect()
+}
+
+/// Applies a PuckerBloat modifier to all paths accumulated before it,
+/// which the styles of its group paint even if they are listed before it.
+///
+/// `amount` is a percentage. Positive values move the vertices towards the center of the path
+/// and the handles away from it (bloat), negative values do the opposite (pucker).
+pub fn pucker_bloat_paths(paths: Vec<Path>, amount: f32) -> Vec<Path> {
+    if amount == 0.0 {
+        return paths;
+    }
+    let amount = amount / 100.0;
+    paths
+        .iter()
+        .filter_map(|path| {
+            let segments = path.segments();
+            let vertices = segments.iter().map(Segment::end).collect::<Vec<_>>();
+            let count = vertices.len().max(1) as f32;
+            let center = vertices.iter().fold([0.0, 0.0], |sum, vertex| {
+                [sum[0] + vertex[0] / count, sum[1] + vertex[1] / count]
+            });
+            let segments = segments
+                .iter()
+                .map(|segment| {
+                    let [a, b, c, d] = cubic_control_points(segment);
+                    Segment::IntegralCubicCurve([
+                        lerp(a, center, amount),
+                        lerp(b, center, -amount),
+                        lerp(c, center, -amount),
+                        lerp(d, center, amount),
+                    ])
+                })
+                .collect::<Vec<_>>();
+            Path::from_segments(&segments)
+        })
+        .collect()
+}
+
+/// Applies a Twist modifier to all paths accumulated before it,
+/// which the styles of its group paint even if they are listed before it.
+///
+/// Points are rotated around `center` by `angle` degrees at the center,
+/// fading out linearly towards the point which is farthest away from it.
+pub fn twist_paths(paths: Vec<Path>, angle: f32, center: [f32; 2]) -> Vec<Path> {
+    if angle == 0.0 {
+        return paths;
+    }
+    let pieces = paths
+        .iter()
+        .map(|path| {
+            path.segments()
+                .iter()
+                .flat_map(|segment| {
+                    (0..TWIST_SUBDIVISIONS).map(move |i| {
+                        cubic_control_points(&segment.sub_segment(
+                            i as f32 / TWIST_SUBDIVISIONS as f32,
+                            (i + 1) as f32 / TWIST_SUBDIVISIONS as f32,
+                        ))
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let radius = pieces
+        .iter()
+        .flatten()
+        .flatten()
+        .map(|point| length(sub(*point, center)))
+        .fold(0.0, f32::max);
+    if radius <= EPSILON {
+        return paths;
+    }
+    let twist = |point: [f32; 2]| {
+        let offset = sub(point, center);
+        let (sin, cos) = (angle.to_radians() * (1.0 - length(offset) / radius)).sin_cos();
+        [
+            center[0] + offset[0] * cos - offset[1] * sin,
+            center[1] + offset[0] * sin + offset[1] * cos,
+        ]
+    };
+    pieces
+        .iter()
+        .filter_map(|pieces| {
+            let segments = pieces
+                .iter()
+                .map(|control_points| Segment::IntegralCubicCurve(control_points.map(twist)))
+                .collect::<Vec<_>>();
+            Path::from_segments(&segments)
+        })
+        .collect()
+}
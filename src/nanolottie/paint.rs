@@ -0,0 +1,164 @@
+//! Evaluation of the colors which styles paint their geometry with
+
+use crate::model::{Gradient, GradientType, Rgb};
+
+use super::transform::{compose, transform_point, Matrix};
+
+/// Color stop of a gradient
+#[derive(Debug, Clone, Copy)]
+pub struct GradientStop {
+    /// Position along the gradient in [0, 1]
+    pub offset: f32,
+    /// Straight (not premultiplied) RGBA
+    pub color: [f32; 4],
+}
+
+/// Gradient in the coordinate system of the [Path](crate::path_rendering::path::Path)s it paints
+#[derive(Debug, Clone)]
+pub struct GradientPaint {
+    pub gradient_type: GradientType,
+    /// Where offset zero is, the center of radial gradients
+    pub start: [f32; 2],
+    /// Where offset one is, on the circle of radial gradients
+    pub end: [f32; 2],
+    /// Focal point of radial gradients, equals `start` for linear gradients
+    pub highlight: [f32; 2],
+    pub stops: Vec<GradientStop>,
+}
+
+impl GradientPaint {
+    /// Interpolates the straight color at an offset, padded with the colors of the first and last stop
+    pub fn color_at(&self, offset: f32) -> [f32; 4] {
+        let Some(first) = self.stops.first() else {
+            return [0.0; 4];
+        };
+        let mut color = first.color;
+        for pair in self.stops.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            if offset >= a.offset {
+                let t = if b.offset > a.offset {
+                    ((offset - a.offset) / (b.offset - a.offset)).min(1.0)
+                } else {
+                    1.0
+                };
+                color = std::array::from_fn(|i| a.color[i] + (b.color[i] - a.color[i]) * t);
+            }
+        }
+        color
+    }
+
+    /// Returns at most `max_stops` stops, gradients with more are sampled at evenly spaced offsets instead
+    pub fn limited_stops(&self, max_stops: usize) -> Vec<GradientStop> {
+        if self.stops.len() <= max_stops {
+            return self.stops.clone();
+        }
+        (0..max_stops)
+            .map(|i| {
+                let offset = i as f32 / (max_stops - 1) as f32;
+                GradientStop {
+                    offset,
+                    color: self.color_at(offset),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Image asset in the coordinate system of the [Path](crate::path_rendering::path::Path)s it paints
+#[derive(Debug, Clone)]
+pub struct ImagePaint {
+    /// Id of the image [Asset](crate::model::Asset)
+    pub asset_id: String,
+    /// Maps texture coordinates in [0, 1] to the coordinate system of the [Path](crate::path_rendering::path::Path)s
+    pub matrix: Matrix,
+    pub opacity: f32,
+}
+
+/// Defines how the area covered by a [DrawCommand](super::DrawCommand) is colored
+#[derive(Debug, Clone)]
+pub enum Paint {
+    /// Straight (not premultiplied) RGBA
+    Solid([f32; 4]),
+    Gradient(GradientPaint),
+    Image(ImagePaint),
+}
+
+impl Paint {
+    pub fn solid(color: Rgb, opacity: f32) -> Self {
+        Self::Solid([
+            color.r as f32 / 255.0,
+            color.g as f32 / 255.0,
+            color.b as f32 / 255.0,
+            (opacity / 100.0).clamp(0.0, 1.0),
+        ])
+    }
+
+    /// Evaluates a Lottie [Gradient] at the given frame.
+    ///
+    /// `highlight_length` (in percent) and `highlight_angle` (in degrees)
+    /// move the focal point of radial gradients away from the center like in lottie-web.
+    pub fn gradient(
+        gradient: &Gradient,
+        highlight_length: f32,
+        highlight_angle: f32,
+        opacity: f32,
+        frame: f32,
+    ) -> Self {
+        let start = gradient.start.value_at(frame);
+        let end = gradient.end.value_at(frame);
+        let (start, end) = ([start.0.x, start.0.y], [end.0.x, end.0.y]);
+        let highlight = match gradient.gradient_ty {
+            GradientType::Linear => start,
+            GradientType::Radial | GradientType::Conical => {
+                let radius = ((end[0] - start[0]).powi(2) + (end[1] - start[1]).powi(2)).sqrt();
+                let angle = (end[1] - start[1]).atan2(end[0] - start[0]);
+                // The focal point has to stay inside of the circle
+                let distance = radius * (highlight_length / 100.0).clamp(-0.99, 0.99);
+                let (sin, cos) = (angle + highlight_angle.to_radians()).sin_cos();
+                [start[0] + cos * distance, start[1] + sin * distance]
+            }
+        };
+        let opacity = (opacity / 100.0).clamp(0.0, 1.0);
+        let stops = gradient
+            .colors
+            .colors
+            .value_at(frame)
+            .iter()
+            .map(|stop| GradientStop {
+                offset: stop.offset,
+                color: [
+                    stop.color.r as f32 / 255.0,
+                    stop.color.g as f32 / 255.0,
+                    stop.color.b as f32 / 255.0,
+                    stop.color.a as f32 / 255.0 * opacity,
+                ],
+            })
+            .collect();
+        Self::Gradient(GradientPaint {
+            gradient_type: gradient.gradient_ty,
+            start,
+            end,
+            highlight,
+            stops,
+        })
+    }
+
+    /// Moves gradients along with the geometry and multiplies the opacity.
+    pub fn transform(&mut self, matrix: &Matrix, opacity: f32) {
+        match self {
+            Self::Solid(color) => color[3] *= opacity,
+            Self::Gradient(gradient) => {
+                gradient.start = transform_point(matrix, gradient.start);
+                gradient.end = transform_point(matrix, gradient.end);
+                gradient.highlight = transform_point(matrix, gradient.highlight);
+                for stop in &mut gradient.stops {
+                    stop.color[3] *= opacity;
+                }
+            }
+            Self::Image(image) => {
+                image.matrix = compose(&[*matrix, image.matrix]);
+                image.opacity *= opacity;
+            }
+        }
+    }
+}
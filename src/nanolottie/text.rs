@@ -0,0 +1,444 @@
+//! Layout of Lottie text documents into glyph [Path]s
+
+use crate::{
+    model::{
+        ease, Animated, Easing, Rgb, Rgba, TextBased, TextCaps, TextDocument, TextJustify,
+        TextRange, TextRangeSelector, TextRangeUnits, TextShape, TextStyle, Vector2D,
+    },
+    path_rendering::{
+        path::Path,
+        text::{paths_of_text, Alignment, Layout, Orientation, TextGeometry},
+        ttf_parser,
+    },
+};
+
+use super::transform::{compose, rotation, scaling, skewing, translation};
+
+/// Size of lowercase letters relative to the font size when they are set in [TextCaps::SmallCaps]
+const SMALL_CAPS_SCALE: f32 = 0.7;
+
+/// Line height relative to the font size if the document does not specify one
+const DEFAULT_LINE_HEIGHT: f32 = 1.2;
+
+/// A character of a [TextDocument] placed in the layer
+pub struct Glyph {
+    /// The character after capitalization
+    pub character: char,
+    /// Index of the character in the text, line breaks excluded
+    pub char_index: usize,
+    pub line_index: usize,
+    /// Center of the advance on the baseline, in layer coordinates
+    pub position: [f32; 2],
+    /// Horizontal advance including tracking
+    pub advance: f32,
+    /// Closed outline relative to `position`, in the y-down coordinate system of Lottie
+    pub paths: Vec<Path>,
+}
+
+/// Outline and colors of a [Glyph] after the text animators were applied
+pub struct StyledGlyph {
+    /// Outline in layer coordinates
+    pub paths: Vec<Path>,
+    /// Straight (not premultiplied) RGBA
+    pub fill_color: [f32; 4],
+    /// Straight (not premultiplied) RGBA
+    pub stroke_color: [f32; 4],
+    pub stroke_width: f32,
+}
+
+/// Lottie font sizes are em sizes, while a [Layout] is relative to the line height of the face
+fn glyph_layout(face: &ttf_parser::Face, font_size: f32) -> Layout {
+    Layout {
+        size: (font_size * face.height() as f32 / face.units_per_em() as f32).into(),
+        orientation: Orientation::LeftToRight,
+        major_alignment: Alignment::Baseline,
+        minor_alignment: Alignment::Baseline,
+    }
+}
+
+/// Applies the capitalization of the document and returns the font size of each resulting char
+fn apply_caps(character: char, caps: TextCaps, font_size: f32) -> Vec<(char, f32)> {
+    match caps {
+        TextCaps::Regular => vec![(character, font_size)],
+        TextCaps::AllCaps => character
+            .to_uppercase()
+            .map(|upper| (upper, font_size))
+            .collect(),
+        TextCaps::SmallCaps if character.is_lowercase() => character
+            .to_uppercase()
+            .map(|upper| (upper, font_size * SMALL_CAPS_SCALE))
+            .collect(),
+        TextCaps::SmallCaps => vec![(character, font_size)],
+    }
+}
+
+/// Places the characters of a [TextDocument] like lottie-web does.
+///
+/// The first baseline is at the origin and every line break (`\r`, `\n` or `\u{3}`) moves down by the line height.
+/// Justification aligns each line to the origin, tracking (in thousandths of an em) is added after every character.
+/// Each character is shaped on its own, so there is no kerning between them.
+pub fn layout_text(face: &ttf_parser::Face, document: &TextDocument) -> Vec<Glyph> {
+    let line_height = document
+        .line_height
+        .unwrap_or(document.size * DEFAULT_LINE_HEIGHT);
+    let tracking = document.tracking * document.size / 1000.0;
+    // Glyph outlines are y-up
+    let flip = scaling(Vector2D::new(100.0, -100.0));
+    let mut glyphs = Vec::new();
+    let mut char_index = 0;
+    for (line_index, line) in document
+        .value
+        .split(|character| matches!(character, '\r' | '\n' | '\u{3}'))
+        .enumerate()
+    {
+        let line_begin = glyphs.len();
+        let baseline = line_index as f32 * line_height - document.baseline_shift;
+        let mut pen = 0.0;
+        for character in line.chars() {
+            for (character, font_size) in apply_caps(character, document.caps, document.size) {
+                let layout = glyph_layout(face, font_size);
+                let text = character.to_string();
+                // paths_of_text centers the glyph on its advance
+                let half_advance = TextGeometry::new(face, &layout, &text).half_extent.unwrap()[0];
+                let mut paths = paths_of_text(face, &layout, &text);
+                for path in &mut paths {
+                    path.close();
+                    path.transform_affine(&flip);
+                }
+                let advance = half_advance * 2.0 + tracking;
+                glyphs.push(Glyph {
+                    character,
+                    char_index,
+                    line_index,
+                    position: [pen + half_advance, baseline],
+                    advance,
+                    paths,
+                });
+                pen += advance;
+            }
+            char_index += 1;
+        }
+        // Paragraph justification needs a text box, without one the last line rules apply to all lines
+        let justify_offset = match document.justify {
+            TextJustify::Left | TextJustify::LastLineLeft | TextJustify::LastLineFull => 0.0,
+            TextJustify::Right | TextJustify::LastLineRight => -pen,
+            TextJustify::Center | TextJustify::LastLineCenter => -pen * 0.5,
+        };
+        for glyph in &mut glyphs[line_begin..] {
+            glyph.position[0] += justify_offset;
+        }
+    }
+    glyphs
+}
+
+/// Returns the index of each [Glyph] in the units the selector is based on and the number of units
+fn selector_indices(glyphs: &[Glyph], based_on: TextBased) -> (Vec<usize>, usize) {
+    let mut indices = Vec::with_capacity(glyphs.len());
+    let mut count = 0;
+    let mut previous: Option<&Glyph> = None;
+    for glyph in glyphs {
+        match based_on {
+            TextBased::Characters => {
+                indices.push(glyph.char_index);
+                count = glyph.char_index + 1;
+            }
+            // Spaces share the index of the following character
+            TextBased::CharactersExcludingSpaces => {
+                indices.push(count);
+                if !glyph.character.is_whitespace() {
+                    count += 1;
+                }
+            }
+            TextBased::Words => {
+                let starts_word = !glyph.character.is_whitespace()
+                    && previous.map_or(true, |previous| {
+                        previous.character.is_whitespace()
+                            || previous.line_index != glyph.line_index
+                    });
+                if starts_word {
+                    count += 1;
+                }
+                indices.push(count.saturating_sub(1));
+            }
+            TextBased::Lines => {
+                indices.push(glyph.line_index);
+                count = glyph.line_index + 1;
+            }
+        }
+        previous = Some(glyph);
+    }
+    (indices, count)
+}
+
+fn value_or(property: &Option<Animated<f32>>, frame: f32, default: f32) -> f32 {
+    property
+        .as_ref()
+        .map_or(default, |property| property.value_at(frame))
+}
+
+/// A [TextRangeSelector] evaluated at a frame, with its range measured in units of [TextBased]
+struct Selection<'a> {
+    selector: &'a TextRangeSelector,
+    start: f32,
+    end: f32,
+    amount: f32,
+    smoothness: f32,
+    easing_out: Easing,
+    easing_in: Easing,
+}
+
+impl<'a> Selection<'a> {
+    fn new(selector: &'a TextRangeSelector, count: usize, frame: f32) -> Self {
+        let count = count as f32;
+        let (divisor, default_end) = match selector.range_units {
+            TextRangeUnits::Percent => (100.0 / count.max(1.0), 100.0),
+            TextRangeUnits::Index => (1.0, count),
+        };
+        let offset = value_or(&selector.offset, frame, 0.0) / divisor;
+        let start = value_or(&selector.start, frame, 0.0) / divisor + offset;
+        let end = value_or(&selector.end, frame, default_end) / divisor + offset;
+        // The ease handles are in percent, positive values flatten the curve at the respective end
+        let min_ease = value_or(&selector.min_ease, frame, 0.0) / 100.0;
+        let max_ease = value_or(&selector.max_ease, frame, 0.0) / 100.0;
+        let (x1, y1) = if min_ease > 0.0 {
+            (min_ease, 0.0)
+        } else {
+            (0.0, -min_ease)
+        };
+        let (x2, y2) = if max_ease > 0.0 {
+            (1.0 - max_ease, 1.0)
+        } else {
+            (1.0, 1.0 + max_ease)
+        };
+        Self {
+            selector,
+            start: start.min(end),
+            end: start.max(end),
+            amount: selector.max_amount.value_at(frame) / 100.0,
+            smoothness: value_or(&selector.selector_smoothness, frame, 100.0) / 100.0,
+            easing_out: Easing {
+                x: vec![x1],
+                y: vec![y1],
+            },
+            easing_in: Easing {
+                x: vec![x2],
+                y: vec![y2],
+            },
+        }
+    }
+
+    /// How much the unit at `index` is selected, like in lottie-web
+    fn coverage(&self, index: f32) -> f32 {
+        let (start, end) = (self.start, self.end);
+        let length = end - start;
+        // Fraction of the range which lies before the center of the unit
+        let ramp = ((index + 0.5 - start) / length).clamp(0.0, 1.0);
+        let coverage = match self.selector.shape {
+            TextShape::Square => {
+                let mut coverage = if index < start.floor() {
+                    0.0
+                } else if index < start {
+                    (end.min(1.0) - (start - index)).clamp(0.0, 1.0)
+                } else {
+                    (end - index).clamp(0.0, 1.0)
+                };
+                if self.smoothness < 1.0 {
+                    let smoothness = self.smoothness.max(f32::EPSILON);
+                    let threshold = 0.5 - smoothness * 0.5;
+                    coverage = if coverage < threshold {
+                        0.0
+                    } else {
+                        ((coverage - threshold) / smoothness).min(1.0)
+                    };
+                }
+                coverage
+            }
+            TextShape::RampUp if length == 0.0 => {
+                if index >= end {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            TextShape::RampUp => ramp,
+            TextShape::RampDown if length == 0.0 => {
+                if index >= end {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+            TextShape::RampDown => 1.0 - ramp,
+            _ if length == 0.0 => 0.0,
+            TextShape::Triangle if ramp < 0.5 => ramp * 2.0,
+            TextShape::Triangle => 1.0 - (ramp - 0.5) * 2.0,
+            TextShape::Round => {
+                let x = (index + 0.5 - start).clamp(0.0, length) - length * 0.5;
+                let radius = length * 0.5;
+                (1.0 - (x * x) / (radius * radius)).max(0.0).sqrt()
+            }
+            TextShape::Smooth => {
+                let x = (index + 0.5 - start).clamp(0.0, length) / length;
+                (1.0 + (std::f32::consts::PI * (1.0 + 2.0 * x)).cos()) * 0.5
+            }
+        };
+        ease(Some(&self.easing_out), Some(&self.easing_in), coverage) * self.amount
+    }
+}
+
+fn rgba(color: Rgba) -> [f32; 4] {
+    [
+        color.r as f32 / 255.0,
+        color.g as f32 / 255.0,
+        color.b as f32 / 255.0,
+        color.a as f32 / 255.0,
+    ]
+}
+
+/// Moves the RGB channels towards `target` and keeps the alpha channel
+fn lerp_color(color: &mut [f32; 4], target: Rgb, coverage: f32) {
+    let target = [target.r, target.g, target.b];
+    for (channel, target) in color.iter_mut().zip(target) {
+        *channel += (target as f32 / 255.0 - *channel) * coverage;
+    }
+}
+
+/// Accumulated effect of all text animators on a [Glyph]
+struct GlyphStyle {
+    anchor: [f32; 2],
+    position: [f32; 2],
+    /// Factors, not percentages
+    scale: [f32; 2],
+    rotation: f32,
+    skew: f32,
+    skew_axis: f32,
+    opacity: f32,
+    fill_color: [f32; 4],
+    stroke_color: [f32; 4],
+    stroke_width: f32,
+}
+
+impl GlyphStyle {
+    fn new(document: &TextDocument) -> Self {
+        Self {
+            anchor: [0.0; 2],
+            position: [0.0; 2],
+            scale: [1.0; 2],
+            rotation: 0.0,
+            skew: 0.0,
+            skew_axis: 0.0,
+            opacity: 1.0,
+            fill_color: rgba(document.fill_color),
+            stroke_color: rgba(document.stroke_color),
+            stroke_width: document.stroke_width,
+        }
+    }
+
+    /// Blends the properties of a [TextStyle] in by the coverage of its selector
+    fn apply(&mut self, style: &TextStyle, coverage: f32, frame: f32) {
+        let vector = |property: &Option<Animated<Vector2D>>| {
+            property.as_ref().map(|property| {
+                let value = property.value_at(frame);
+                [value.0.x, value.0.y]
+            })
+        };
+        if let Some(anchor) = vector(&style.anchor) {
+            self.anchor[0] += anchor[0] * coverage;
+            self.anchor[1] += anchor[1] * coverage;
+        }
+        if let Some(position) = vector(&style.position) {
+            self.position[0] += position[0] * coverage;
+            self.position[1] += position[1] * coverage;
+        }
+        if let Some(scale) = vector(&style.scale) {
+            self.scale[0] *= 1.0 + (scale[0] / 100.0 - 1.0) * coverage;
+            self.scale[1] *= 1.0 + (scale[1] / 100.0 - 1.0) * coverage;
+        }
+        self.rotation += value_or(&style.rotation, frame, 0.0) * coverage;
+        self.skew += value_or(&style.skew, frame, 0.0) * coverage;
+        self.skew_axis += value_or(&style.skew_axis, frame, 0.0) * coverage;
+        if let Some(opacity) = &style.opacity {
+            self.opacity *= 1.0 + (opacity.value_at(frame) / 100.0 - 1.0) * coverage;
+        }
+        if let Some(fill_color) = &style.fill_color {
+            lerp_color(&mut self.fill_color, fill_color.value_at(frame), coverage);
+        }
+        if let Some(stroke_color) = &style.stroke_color {
+            lerp_color(
+                &mut self.stroke_color,
+                stroke_color.value_at(frame),
+                coverage,
+            );
+        }
+        self.stroke_width += value_or(&style.stroke_width, frame, 0.0) * coverage;
+    }
+}
+
+/// Evaluates the text animators of a text layer and applies them to every [Glyph].
+///
+/// Each animator blends its [TextStyle] in by how much its selector covers a glyph.
+/// The glyphs are rotated, skewed and scaled around their anchor, which is the center of their advance on the baseline.
+/// Letter spacing moves all following glyphs of the same line.
+pub fn animate_glyphs(
+    glyphs: &[Glyph],
+    ranges: &[TextRange],
+    document: &TextDocument,
+    frame: f32,
+) -> Vec<StyledGlyph> {
+    let mut styles = glyphs
+        .iter()
+        .map(|_| GlyphStyle::new(document))
+        .collect::<Vec<_>>();
+    for range in ranges {
+        let Some(style) = &range.style else {
+            continue;
+        };
+        let (indices, count) = selector_indices(glyphs, range.selector.based_on);
+        let selection = Selection::new(&range.selector, count, frame);
+        let mut letter_spacing = 0.0;
+        let mut line_index = None;
+        for ((glyph, index), glyph_style) in glyphs.iter().zip(indices).zip(&mut styles) {
+            if line_index != Some(glyph.line_index) {
+                line_index = Some(glyph.line_index);
+                letter_spacing = 0.0;
+            }
+            let coverage = selection.coverage(index as f32);
+            glyph_style.apply(style, coverage, frame);
+            glyph_style.position[0] += letter_spacing;
+            letter_spacing += value_or(&style.letter_spacing, frame, 0.0) * coverage;
+        }
+    }
+    glyphs
+        .iter()
+        .zip(styles)
+        .map(|(glyph, style)| {
+            let matrix = compose(&[
+                translation(Vector2D::new(
+                    glyph.position[0] + style.position[0],
+                    glyph.position[1] + style.position[1],
+                )),
+                rotation(style.rotation),
+                skewing(style.skew, style.skew_axis),
+                scaling(Vector2D::new(
+                    style.scale[0] * 100.0,
+                    style.scale[1] * 100.0,
+                )),
+                translation(Vector2D::new(-style.anchor[0], -style.anchor[1])),
+            ]);
+            let mut paths = glyph.paths.clone();
+            for path in &mut paths {
+                path.transform_affine(&matrix);
+            }
+            let opacity = style.opacity.clamp(0.0, 1.0);
+            let (mut fill_color, mut stroke_color) = (style.fill_color, style.stroke_color);
+            fill_color[3] *= opacity;
+            stroke_color[3] *= opacity;
+            StyledGlyph {
+                paths,
+                fill_color,
+                stroke_color,
+                stroke_width: style.stroke_width.max(0.0),
+            }
+        })
+        .collect()
+}
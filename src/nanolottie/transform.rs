@@ -0,0 +1,200 @@
+//! Affine 2D matrices of Lottie transforms
+
+use crate::{
+    model::{Repeater, RepeaterTransform, Transform, Vector2D},
+    path_rendering::utils::mat3_multiplication,
+};
+use geometric_algebra::ppga2d;
+
+/// Column major 3x3 matrix as used by [Path::transform_affine](crate::path_rendering::path::Path::transform_affine)
+pub type Matrix = [ppga2d::Point; 3];
+
+pub fn identity() -> Matrix {
+    [
+        ppga2d::Point::new(1.0, 0.0, 0.0),
+        ppga2d::Point::new(0.0, 1.0, 0.0),
+        ppga2d::Point::new(0.0, 0.0, 1.0),
+    ]
+}
+
+pub fn translation(offset: Vector2D) -> Matrix {
+    [
+        ppga2d::Point::new(1.0, 0.0, 0.0),
+        ppga2d::Point::new(0.0, 1.0, 0.0),
+        ppga2d::Point::new(offset.0.x, offset.0.y, 1.0),
+    ]
+}
+
+/// Positive angles (in degrees) rotate clockwise in the y-down coordinate system of Lottie.
+pub fn rotation(angle: f32) -> Matrix {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    [
+        ppga2d::Point::new(cos, sin, 0.0),
+        ppga2d::Point::new(-sin, cos, 0.0),
+        ppga2d::Point::new(0.0, 0.0, 1.0),
+    ]
+}
+
+/// Scale factors are percentages.
+pub fn scaling(scale: Vector2D) -> Matrix {
+    [
+        ppga2d::Point::new(scale.0.x / 100.0, 0.0, 0.0),
+        ppga2d::Point::new(0.0, scale.0.y / 100.0, 0.0),
+        ppga2d::Point::new(0.0, 0.0, 1.0),
+    ]
+}
+
+/// Shears by `skew` degrees along the direction `skew_axis` degrees.
+pub fn skewing(skew: f32, skew_axis: f32) -> Matrix {
+    let shear = [
+        ppga2d::Point::new(1.0, 0.0, 0.0),
+        ppga2d::Point::new(-skew.to_radians().tan(), 1.0, 0.0),
+        ppga2d::Point::new(0.0, 0.0, 1.0),
+    ];
+    mat3_multiplication(
+        &rotation(-skew_axis),
+        &mat3_multiplication(&shear, &rotation(skew_axis)),
+    )
+}
+
+/// Returns the product of all `matrices`, the last one is applied first.
+pub fn compose(matrices: &[Matrix]) -> Matrix {
+    matrices.iter().fold(identity(), |result, matrix| {
+        mat3_multiplication(&result, matrix)
+    })
+}
+
+/// Applies a [Matrix] to a point.
+pub fn transform_point(matrix: &Matrix, point: [f32; 2]) -> [f32; 2] {
+    [
+        matrix[2][0] + point[0] * matrix[0][0] + point[1] * matrix[1][0],
+        matrix[2][1] + point[0] * matrix[0][1] + point[1] * matrix[1][1],
+    ]
+}
+
+/// The factor by which the [Matrix] scales lengths on average, which is used for stroke widths
+pub fn average_scale(matrix: &Matrix) -> f32 {
+    (matrix[0][0] * matrix[1][1] - matrix[0][1] * matrix[1][0])
+        .abs()
+        .sqrt()
+}
+
+/// The inverse of an affine [Matrix], or [None] if it is singular
+pub fn inverse(matrix: &Matrix) -> Option<Matrix> {
+    let determinant = matrix[0][0] * matrix[1][1] - matrix[0][1] * matrix[1][0];
+    if determinant == 0.0 {
+        return None;
+    }
+    let inverse_determinant = 1.0 / determinant;
+    let (a, b, c, d) = (
+        matrix[1][1] * inverse_determinant,
+        -matrix[0][1] * inverse_determinant,
+        -matrix[1][0] * inverse_determinant,
+        matrix[0][0] * inverse_determinant,
+    );
+    let (x, y) = (matrix[2][0], matrix[2][1]);
+    Some([
+        ppga2d::Point::new(a, b, 0.0),
+        ppga2d::Point::new(c, d, 0.0),
+        ppga2d::Point::new(-a * x - c * y, -b * x - d * y, 1.0),
+    ])
+}
+
+/// Evaluates a [Transform] at the given frame.
+///
+/// Like in After Effects the anchor is moved to the origin first,
+/// then scale, skew and rotation are applied, and finally the result is moved to the position.
+/// Auto-oriented transforms additionally rotate along the direction of motion.
+pub fn transform_matrix(transform: &Transform, frame: f32) -> Matrix {
+    let anchor = transform
+        .anchor
+        .as_ref()
+        .map(|anchor| anchor.value_at(frame))
+        .unwrap_or_default();
+    let position = transform.position_at(frame);
+    let skew = transform
+        .skew
+        .as_ref()
+        .map(|skew| skew.value_at(frame))
+        .unwrap_or_default();
+    let skew_axis = transform
+        .skew_axis
+        .as_ref()
+        .map(|skew_axis| skew_axis.value_at(frame))
+        .unwrap_or_default();
+    let mut angle = transform.rotation.value_at(frame);
+    if transform.auto_orient {
+        angle += transform.orientation_at(frame);
+    }
+    compose(&[
+        translation(position),
+        rotation(angle),
+        skewing(skew, skew_axis),
+        scaling(transform.scale.value_at(frame)),
+        translation(Vector2D::new(-anchor.0.x, -anchor.0.y)),
+    ])
+}
+
+/// The transform between two consecutive copies of a [Repeater], scaled down to a fraction of a step
+fn repeater_step(transform: &RepeaterTransform, frame: f32, fraction: f32) -> Matrix {
+    let anchor = transform
+        .anchor
+        .as_ref()
+        .map(|anchor| anchor.value_at(frame))
+        .unwrap_or_default();
+    let position = transform
+        .position
+        .as_ref()
+        .map(|position| position.value_at(frame))
+        .unwrap_or_default();
+    let scale = transform.scale.value_at(frame);
+    compose(&[
+        translation(Vector2D::new(
+            position.0.x * fraction,
+            position.0.y * fraction,
+        )),
+        translation(anchor),
+        rotation(transform.rotation.value_at(frame) * fraction),
+        scaling(Vector2D::new(
+            100.0 + (scale.0.x - 100.0) * fraction,
+            100.0 + (scale.0.y - 100.0) * fraction,
+        )),
+        translation(Vector2D::new(-anchor.0.x, -anchor.0.y)),
+    ])
+}
+
+/// Evaluates a [Repeater] at the given frame.
+///
+/// Returns the matrix and the opacity (in [0, 1]) of every copy, starting with the original.
+/// Each copy applies the step transform once more than the previous one,
+/// the offset shifts all copies by a (possibly fractional or negative) number of steps.
+pub fn repeater_copies(repeater: &Repeater, frame: f32) -> Vec<(Matrix, f32)> {
+    let copies = repeater.copies.value_at(frame).ceil().max(0.0) as usize;
+    let offset = repeater.offset.value_at(frame);
+    let transform = &repeater.transform;
+    let step = repeater_step(transform, frame, 1.0);
+    let whole_steps = offset.floor();
+    let mut matrix = repeater_step(transform, frame, offset - whole_steps);
+    let offset_step = if whole_steps < 0.0 {
+        inverse(&step).unwrap_or_else(identity)
+    } else {
+        step
+    };
+    for _ in 0..whole_steps.abs() as usize {
+        matrix = mat3_multiplication(&offset_step, &matrix);
+    }
+    let start_opacity = transform.start_opacity.value_at(frame) / 100.0;
+    let end_opacity = transform.end_opacity.value_at(frame) / 100.0;
+    let mut result = Vec::with_capacity(copies);
+    for index in 0..copies {
+        let ratio = if copies > 1 {
+            index as f32 / (copies - 1) as f32
+        } else {
+            0.0
+        };
+        let opacity = start_opacity + (end_opacity - start_opacity) * ratio;
+        result.push((matrix, opacity.clamp(0.0, 1.0)));
+        matrix = mat3_multiplication(&step, &matrix);
+    }
+    result
+}
@@ -1,36 +1,742 @@
 // Most of it copied from https://github.com/Lichtso/contrast_renderer
+pub mod boolean;
 mod curve;
 mod error;
 mod fill;
-mod path;
-mod renderer;
+pub mod path;
+pub mod renderer;
 mod safe_float;
 mod stroke;
 pub mod utils;
 pub mod vertex;
 pub extern crate ttf_parser;
-mod text;
+pub mod text;
 
-const OPEN_SANS_TTF: &[u8] = include_bytes!("../fonts/OpenSans-Regular.ttf");
+pub const OPEN_SANS_TTF: &[u8] = include_bytes!("../fonts/OpenSans-Regular.ttf");
 
 pub mod raw_miniquad {
-    use super::path::{LineSegment, Path};
-    use super::renderer::Shape;
+    use super::path::{Cap, DynamicStrokeOptions, Join, LineSegment, Path, MAX_DASH_INTERVALS};
+    use super::renderer::{Blending, FillRule, Shape, MAX_DYNAMIC_STROKE_OPTIONS};
     use super::text::{paths_of_text, Alignment, Layout, Orientation};
-    use super::vertex::{Vertex0, Vertex2f, Vertex3f};
+    use super::utils::{mat3_inverse, mat3_multiplication, matrix_multiplication, motor3d_to_mat4};
+    use super::vertex::{Vertex0, Vertex2f, Vertex2f1i, Vertex3f, Vertex3f1i, Vertex4f};
     use super::OPEN_SANS_TTF;
+    use geometric_algebra::{
+        ppga2d,
+        ppga3d::{self, Rotor, Translator},
+        GeometricProduct, One,
+    };
     use macroquad::miniquad::*;
 
+    // 6x subpixel AA pattern
+    //
+    //   R = (f(x - 2/3, y) + f(x - 1/3, y) + f(x, y)) / 3
+    //   G = (f(x - 1/3, y) + f(x, y) + f(x + 1/3, y)) / 3
+    //   B = (f(x, y) + f(x + 1/3, y) + f(x + 2/3, y)) / 3
+    //
+    // The shader would require three texture lookups if the texture format
+    // stored data for offsets -1/3, 0, and +1/3 since the shader also needs
+    // data for offsets -2/3 and +2/3. To avoid this, the texture format stores
+    // data for offsets 0, +1/3, and +2/3 instead. That way the shader can get
+    // data for offsets -2/3 and -1/3 with only one additional texture lookup.
+    //
+    pub const JITTER_PATTERN: [[f32; 2]; 6] = [
+        [-1. / 12.0, -5. / 12.0],
+        [1. / 12.0, 1. / 12.0],
+        [3. / 12.0, -1. / 12.0],
+        [5. / 12.0, 5. / 12.0],
+        [7. / 12.0, -3. / 12.0],
+        [9. / 12.0, 3. / 12.0],
+    ];
+
+    /// GPU buffers of one [Shape], separated by the pipeline which renders them
+    pub struct ShapeBindings {
+        pub fill_solid_bindings: Bindings,
+        pub fill_solid_count: i32,
+        pub fill_integral_quadratic_curve_bindings: Bindings,
+        pub fill_integral_quadratic_curve_count: i32,
+        pub fill_integral_cubic_curve_bindings: Bindings,
+        pub fill_integral_cubic_curve_count: i32,
+        pub fill_rational_quadratic_curve_bindings: Bindings,
+        pub fill_rational_quadratic_curve_count: i32,
+        pub fill_rational_cubic_curve_bindings: Bindings,
+        pub fill_rational_cubic_curve_count: i32,
+        pub stroke_line_bindings: Bindings,
+        pub stroke_line_count: i32,
+        pub stroke_joint_bindings: Bindings,
+        pub stroke_joint_count: i32,
+        /// [STROKE_OPTIONS_VECTORS] per [DynamicStrokeOptions] group, see [stroke_options_uniform]
+        pub stroke_options: [[f32; 4]; STROKE_OPTIONS_VECTORS * MAX_DYNAMIC_STROKE_OPTIONS],
+        pub convex_box: Vec<f32>,
+    }
+
+    /// Index of a [Join] in the stroke shaders, in declaration order
+    fn join_index(join: Join) -> f32 {
+        match join {
+            Join::Miter => 0.0,
+            Join::Bevel => 1.0,
+            Join::Round => 2.0,
+        }
+    }
+
+    /// Index of a [Cap] in the stroke shaders, in declaration order
+    fn cap_index(cap: Cap) -> f32 {
+        match cap {
+            Cap::Square => 0.0,
+            Cap::Round => 1.0,
+            Cap::Out => 2.0,
+            Cap::In => 3.0,
+            Cap::Right => 4.0,
+            Cap::Left => 5.0,
+            Cap::Butt => 6.0,
+        }
+    }
+
+    /// Number of uniform vectors per [DynamicStrokeOptions] group
+    pub const STROKE_OPTIONS_VECTORS: usize = 6;
+
+    /// Packs [DynamicStrokeOptions] into uniform vectors:
+    ///
+    /// - The join, the start cap and the end cap of solid strokes
+    /// - The number of [DashInterval](super::path::DashInterval)s (zero for solid strokes), the period and the phase
+    /// - The gap starts, the gap ends, the dash start caps and the dash end caps of the intervals
+    fn stroke_options_uniform(
+        dynamic_stroke_options: &DynamicStrokeOptions,
+    ) -> [[f32; 4]; STROKE_OPTIONS_VECTORS] {
+        let mut uniform = [[0.0; 4]; STROKE_OPTIONS_VECTORS];
+        match dynamic_stroke_options {
+            DynamicStrokeOptions::Solid { join, start, end } => {
+                uniform[0] = [join_index(*join), cap_index(*start), cap_index(*end), 0.0];
+            }
+            DynamicStrokeOptions::Dashed {
+                join,
+                pattern,
+                phase,
+            } => {
+                uniform[0] = [
+                    join_index(*join),
+                    cap_index(Cap::Butt),
+                    cap_index(Cap::Butt),
+                    0.0,
+                ];
+                let intervals = &pattern[..pattern.len().min(MAX_DASH_INTERVALS)];
+                let period = intervals
+                    .last()
+                    .map_or(0.0, |interval| interval.gap_end.unwrap());
+                // Without a period the stroke stays solid
+                if period > 0.0 {
+                    uniform[1] = [intervals.len() as f32, period, phase.unwrap(), 0.0];
+                }
+                for (i, interval) in intervals.iter().enumerate() {
+                    uniform[2][i] = interval.gap_start.unwrap();
+                    uniform[3][i] = interval.gap_end.unwrap();
+                    uniform[4][i] = cap_index(interval.dash_start);
+                    uniform[5][i] = cap_index(interval.dash_end);
+                }
+            }
+        }
+        uniform
+    }
+
+    /// The vertex buffer is streamed, so that [ShapeBindings::update] can refill it
+    fn new_shape_bindings(
+        ctx: &mut dyn RenderingBackend,
+        shape: &Shape,
+        begin_offset: usize,
+        end_offset: usize,
+        vertex_size: usize,
+    ) -> (Bindings, i32) {
+        let vertices = &shape.vertex_buffer[begin_offset..end_offset];
+        let vertex_buffer = ctx.new_buffer(
+            BufferType::VertexBuffer,
+            BufferUsage::Stream,
+            BufferSource::slice(vertices),
+        );
+        let count = (end_offset - begin_offset) / vertex_size;
+        let indices: Vec<u16> = (0..count as u16).collect();
+
+        let index_buffer = ctx.new_buffer(
+            BufferType::IndexBuffer,
+            BufferUsage::Immutable,
+            BufferSource::slice(&indices),
+        );
+
+        (
+            Bindings {
+                vertex_buffers: vec![vertex_buffer],
+                index_buffer,
+                images: vec![],
+            },
+            count as i32,
+        )
+    }
+
+    /// Refills the buffers of [new_shape_bindings], they are only replaced if they are too small.
+    ///
+    /// The indices simply count up, so a larger index buffer already starts with the right ones.
+    fn update_shape_bindings(
+        ctx: &mut dyn RenderingBackend,
+        bindings: &mut Bindings,
+        shape: &Shape,
+        begin_offset: usize,
+        end_offset: usize,
+        vertex_size: usize,
+    ) -> i32 {
+        let count = (end_offset - begin_offset) / vertex_size;
+        if ctx.buffer_size(bindings.vertex_buffers[0]) < end_offset - begin_offset
+            || ctx.buffer_size(bindings.index_buffer) < count * std::mem::size_of::<u16>()
+        {
+            ctx.delete_buffer(bindings.vertex_buffers[0]);
+            ctx.delete_buffer(bindings.index_buffer);
+            let (new_bindings, count) =
+                new_shape_bindings(ctx, shape, begin_offset, end_offset, vertex_size);
+            *bindings = new_bindings;
+            return count;
+        }
+        ctx.buffer_update(
+            bindings.vertex_buffers[0],
+            BufferSource::slice(&shape.vertex_buffer[begin_offset..end_offset]),
+        );
+        count as i32
+    }
+
+    impl ShapeBindings {
+        pub fn new(ctx: &mut dyn RenderingBackend, shape: &Shape) -> ShapeBindings {
+            let (fill_solid_bindings, fill_solid_count) = new_shape_bindings(
+                ctx,
+                shape,
+                0,
+                shape.vertex_offsets[0],
+                std::mem::size_of::<Vertex0>(),
+            );
+            let (fill_integral_quadratic_curve_bindings, fill_integral_quadratic_curve_count) =
+                new_shape_bindings(
+                    ctx,
+                    shape,
+                    shape.vertex_offsets[0],
+                    shape.vertex_offsets[1],
+                    std::mem::size_of::<Vertex2f>(),
+                );
+            let (fill_integral_cubic_curve_bindings, fill_integral_cubic_curve_count) =
+                new_shape_bindings(
+                    ctx,
+                    shape,
+                    shape.vertex_offsets[1],
+                    shape.vertex_offsets[2],
+                    std::mem::size_of::<Vertex3f>(),
+                );
+            let (fill_rational_quadratic_curve_bindings, fill_rational_quadratic_curve_count) =
+                new_shape_bindings(
+                    ctx,
+                    shape,
+                    shape.vertex_offsets[2],
+                    shape.vertex_offsets[3],
+                    std::mem::size_of::<Vertex3f>(),
+                );
+            let (fill_rational_cubic_curve_bindings, fill_rational_cubic_curve_count) =
+                new_shape_bindings(
+                    ctx,
+                    shape,
+                    shape.vertex_offsets[3],
+                    shape.vertex_offsets[4],
+                    std::mem::size_of::<Vertex4f>(),
+                );
+            let (stroke_line_bindings, stroke_line_count) = new_shape_bindings(
+                ctx,
+                shape,
+                shape.vertex_offsets[4],
+                shape.vertex_offsets[5],
+                std::mem::size_of::<Vertex2f1i>(),
+            );
+            let (stroke_joint_bindings, stroke_joint_count) = new_shape_bindings(
+                ctx,
+                shape,
+                shape.vertex_offsets[5],
+                shape.vertex_offsets[6],
+                std::mem::size_of::<Vertex3f1i>(),
+            );
+            let mut stroke_options =
+                [[0.0; 4]; STROKE_OPTIONS_VECTORS * MAX_DYNAMIC_STROKE_OPTIONS];
+            for (uniform, dynamic_stroke_options) in stroke_options
+                .chunks_exact_mut(STROKE_OPTIONS_VECTORS)
+                .zip(shape.dynamic_stroke_options.iter())
+            {
+                uniform.copy_from_slice(&stroke_options_uniform(dynamic_stroke_options));
+            }
+            ShapeBindings {
+                fill_solid_bindings,
+                fill_solid_count,
+                fill_integral_quadratic_curve_bindings,
+                fill_integral_quadratic_curve_count,
+                fill_integral_cubic_curve_bindings,
+                fill_integral_cubic_curve_count,
+                fill_rational_quadratic_curve_bindings,
+                fill_rational_quadratic_curve_count,
+                fill_rational_cubic_curve_bindings,
+                fill_rational_cubic_curve_count,
+                stroke_line_bindings,
+                stroke_line_count,
+                stroke_joint_bindings,
+                stroke_joint_count,
+                stroke_options,
+                convex_box: shape.convex_box.clone(),
+            }
+        }
+
+        /// Replaces the [Shape], reusing the GPU buffers where they are large enough
+        pub fn update(&mut self, ctx: &mut dyn RenderingBackend, shape: &Shape) {
+            for (bindings, count, begin_offset, end_offset, vertex_size) in [
+                (
+                    &mut self.fill_solid_bindings,
+                    &mut self.fill_solid_count,
+                    0,
+                    shape.vertex_offsets[0],
+                    std::mem::size_of::<Vertex0>(),
+                ),
+                (
+                    &mut self.fill_integral_quadratic_curve_bindings,
+                    &mut self.fill_integral_quadratic_curve_count,
+                    shape.vertex_offsets[0],
+                    shape.vertex_offsets[1],
+                    std::mem::size_of::<Vertex2f>(),
+                ),
+                (
+                    &mut self.fill_integral_cubic_curve_bindings,
+                    &mut self.fill_integral_cubic_curve_count,
+                    shape.vertex_offsets[1],
+                    shape.vertex_offsets[2],
+                    std::mem::size_of::<Vertex3f>(),
+                ),
+                (
+                    &mut self.fill_rational_quadratic_curve_bindings,
+                    &mut self.fill_rational_quadratic_curve_count,
+                    shape.vertex_offsets[2],
+                    shape.vertex_offsets[3],
+                    std::mem::size_of::<Vertex3f>(),
+                ),
+                (
+                    &mut self.fill_rational_cubic_curve_bindings,
+                    &mut self.fill_rational_cubic_curve_count,
+                    shape.vertex_offsets[3],
+                    shape.vertex_offsets[4],
+                    std::mem::size_of::<Vertex4f>(),
+                ),
+                (
+                    &mut self.stroke_line_bindings,
+                    &mut self.stroke_line_count,
+                    shape.vertex_offsets[4],
+                    shape.vertex_offsets[5],
+                    std::mem::size_of::<Vertex2f1i>(),
+                ),
+                (
+                    &mut self.stroke_joint_bindings,
+                    &mut self.stroke_joint_count,
+                    shape.vertex_offsets[5],
+                    shape.vertex_offsets[6],
+                    std::mem::size_of::<Vertex3f1i>(),
+                ),
+            ] {
+                *count = update_shape_bindings(
+                    ctx,
+                    bindings,
+                    shape,
+                    begin_offset,
+                    end_offset,
+                    vertex_size,
+                );
+            }
+            self.stroke_options = [[0.0; 4]; STROKE_OPTIONS_VECTORS * MAX_DYNAMIC_STROKE_OPTIONS];
+            for (uniform, dynamic_stroke_options) in self
+                .stroke_options
+                .chunks_exact_mut(STROKE_OPTIONS_VECTORS)
+                .zip(shape.dynamic_stroke_options.iter())
+            {
+                uniform.copy_from_slice(&stroke_options_uniform(dynamic_stroke_options));
+            }
+            self.convex_box.clone_from(&shape.convex_box);
+        }
+
+        /// Frees the GPU buffers
+        pub fn delete(self, ctx: &mut dyn RenderingBackend) {
+            for bindings in [
+                self.fill_solid_bindings,
+                self.fill_integral_quadratic_curve_bindings,
+                self.fill_integral_cubic_curve_bindings,
+                self.fill_rational_quadratic_curve_bindings,
+                self.fill_rational_cubic_curve_bindings,
+                self.stroke_line_bindings,
+                self.stroke_joint_bindings,
+            ] {
+                for vertex_buffer in bindings.vertex_buffers {
+                    ctx.delete_buffer(vertex_buffer);
+                }
+                ctx.delete_buffer(bindings.index_buffer);
+            }
+        }
+    }
+
+    /// Creates a pipeline which accumulates the coverage of a [Shape] with additive blending,
+    /// or which only flips the parity of the covered samples in the stencil buffer if `parity` is set
+    fn new_fill_pipeline(
+        ctx: &mut dyn RenderingBackend,
+        vertex_shader: &str,
+        fragment_shader: &str,
+        metal_shader: &str,
+        meta: ShaderMeta,
+        attributes: &[VertexAttribute],
+        parity: bool,
+    ) -> Pipeline {
+        let shader = ctx
+            .new_shader(
+                match ctx.info().backend {
+                    Backend::OpenGl => ShaderSource::Glsl {
+                        vertex: vertex_shader,
+                        fragment: fragment_shader,
+                    },
+                    Backend::Metal => ShaderSource::Msl {
+                        program: metal_shader,
+                    },
+                },
+                meta,
+            )
+            .unwrap();
+
+        ctx.new_pipeline(
+            &[BufferLayout::default()],
+            attributes,
+            shader,
+            if parity {
+                PipelineParams {
+                    primitive_type: PrimitiveType::Triangles,
+                    color_write: (false, false, false, false),
+                    stencil_test: Some(stencil_state(CompareFunc::Always, StencilOp::Invert, 1)),
+                    ..Default::default()
+                }
+            } else {
+                PipelineParams {
+                    primitive_type: PrimitiveType::Triangles,
+                    color_blend: Some(BlendState::new(
+                        Equation::Add,
+                        BlendFactor::One,
+                        BlendFactor::One,
+                    )),
+                    ..Default::default()
+                }
+            },
+        )
+    }
+
+    /// OpenGL constant which miniquad does not export
+    const GL_STENCIL_INDEX8: u32 = 0x8D48;
+
+    /// Creates a render pass into a texture with a stencil buffer, which is used for clipping and even-odd.
+    ///
+    /// The depth textures of miniquad have no stencil bits, so a stencil renderbuffer is attached
+    /// to the framebuffer of the pass directly. Free it with [delete_stencil_buffer] along with the pass.
+    pub fn new_stencil_render_pass(
+        ctx: &mut dyn RenderingBackend,
+        texture: TextureId,
+        width: u32,
+        height: u32,
+    ) -> (RenderPass, u32) {
+        let render_pass = ctx.new_render_pass(texture, None);
+        if ctx.info().backend != Backend::OpenGl {
+            return (render_pass, 0);
+        }
+        let mut stencil_buffer = 0;
+        // Beginning the pass binds its framebuffer
+        ctx.begin_pass(Some(render_pass), PassAction::Nothing);
+        unsafe {
+            gl::glGenRenderbuffers(1, &mut stencil_buffer);
+            gl::glBindRenderbuffer(gl::GL_RENDERBUFFER, stencil_buffer);
+            gl::glRenderbufferStorage(
+                gl::GL_RENDERBUFFER,
+                GL_STENCIL_INDEX8,
+                width as i32,
+                height as i32,
+            );
+            gl::glFramebufferRenderbuffer(
+                gl::GL_FRAMEBUFFER,
+                gl::GL_STENCIL_ATTACHMENT,
+                gl::GL_RENDERBUFFER,
+                stencil_buffer,
+            );
+            gl::glBindRenderbuffer(gl::GL_RENDERBUFFER, 0);
+        }
+        ctx.end_render_pass();
+        (render_pass, stencil_buffer)
+    }
+
+    /// Frees a stencil buffer of [new_stencil_render_pass]
+    pub fn delete_stencil_buffer(stencil_buffer: u32) {
+        if stencil_buffer != 0 {
+            unsafe { gl::glDeleteRenderbuffers(1, &stencil_buffer) };
+        }
+    }
+
+    /// Creates a pipeline which draws a screen filling quad to read back offscreen textures
+    fn new_cover_pipeline(
+        ctx: &mut dyn RenderingBackend,
+        fragment_shader: &str,
+        metal_shader: &str,
+        meta: ShaderMeta,
+        params: PipelineParams,
+    ) -> Pipeline {
+        let shader = ctx
+            .new_shader(
+                match ctx.info().backend {
+                    Backend::OpenGl => ShaderSource::Glsl {
+                        vertex: shader::COVER_VERTEX,
+                        fragment: fragment_shader,
+                    },
+                    Backend::Metal => ShaderSource::Msl {
+                        program: metal_shader,
+                    },
+                },
+                meta,
+            )
+            .unwrap();
+
+        ctx.new_pipeline(
+            &[BufferLayout::default()],
+            &[
+                VertexAttribute::new("position", VertexFormat::Float2),
+                VertexAttribute::new("in_uv", VertexFormat::Float2),
+            ],
+            shader,
+            params,
+        )
+    }
+
+    /// The [Blending]s which the cover pipelines implement with blend states
+    pub const FIXED_BLENDINGS: [Blending; 4] = [
+        Blending::Normal,
+        Blending::Multiply,
+        Blending::Screen,
+        Blending::Add,
+    ];
+
+    /// Blend state of a fixed-function [Blending] for premultiplied colors
+    ///
+    /// Multiply assumes an opaque backdrop, the others are exact.
+    /// Offscreen contexts start transparent, so [Stage::draw_shape] does not use Multiply and Screen there.
+    fn blend_state(blending: Blending) -> BlendState {
+        let (source, destination) = match blending {
+            Blending::Multiply => (
+                BlendFactor::Value(BlendValue::DestinationColor),
+                BlendFactor::OneMinusValue(BlendValue::SourceAlpha),
+            ),
+            Blending::Screen => (
+                BlendFactor::One,
+                BlendFactor::OneMinusValue(BlendValue::SourceColor),
+            ),
+            Blending::Add => (BlendFactor::One, BlendFactor::One),
+            _ => (
+                BlendFactor::One,
+                BlendFactor::OneMinusValue(BlendValue::SourceAlpha),
+            ),
+        };
+        BlendState::new(Equation::Add, source, destination)
+    }
+
+    /// Selects the blend function of the blend shader, which reproduces the layer for [Blending::Normal]
+    fn blend_mode(blending: Blending) -> [f32; 4] {
+        let mode = match blending {
+            Blending::Overlay => 0.0,
+            Blending::Darken => 1.0,
+            Blending::Lighten => 2.0,
+            Blending::Difference => 3.0,
+            Blending::Multiply => 4.0,
+            Blending::Screen => 5.0,
+            _ => 6.0,
+        };
+        [mode, 0.0, 0.0, 0.0]
+    }
+
+    /// One cover pipeline per fixed-function [Blending] in the order of [FIXED_BLENDINGS],
+    /// once for everywhere and once only inside of the current clip area
+    pub struct CoverPipelines {
+        pub unclipped: [Pipeline; 4],
+        pub clipped: [Pipeline; 4],
+    }
+
+    impl CoverPipelines {
+        fn new(
+            ctx: &mut dyn RenderingBackend,
+            fragment_shader: &str,
+            metal_shader: &str,
+            meta: fn() -> ShaderMeta,
+        ) -> CoverPipelines {
+            let mut new_pipelines = |stencil_test: Option<StencilState>| {
+                FIXED_BLENDINGS.map(|blending| {
+                    new_cover_pipeline(
+                        ctx,
+                        fragment_shader,
+                        metal_shader,
+                        meta(),
+                        PipelineParams {
+                            primitive_type: PrimitiveType::Triangles,
+                            color_blend: Some(blend_state(blending)),
+                            alpha_blend: Some(blend_state(Blending::Normal)),
+                            stencil_test,
+                            ..Default::default()
+                        },
+                    )
+                })
+            };
+            CoverPipelines {
+                unclipped: new_pipelines(None),
+                clipped: new_pipelines(Some(stencil_state(CompareFunc::Equal, StencilOp::Keep, 0))),
+            }
+        }
+
+        fn get(&self, blending: Blending, clipped: bool) -> &Pipeline {
+            let index = FIXED_BLENDINGS
+                .iter()
+                .position(|fixed_blending| *fixed_blending == blending)
+                .unwrap_or(0);
+            if clipped {
+                &self.clipped[index]
+            } else {
+                &self.unclipped[index]
+            }
+        }
+    }
+
+    /// How the cover pass colors the area of a [Shape]
+    pub enum CoverPaint {
+        /// Straight (not premultiplied) RGBA
+        Color([f32; 4]),
+        /// Straight RGBA texture, `matrix` maps its texture coordinates to the coordinate system of the [Shape]
+        Image {
+            texture: TextureId,
+            matrix: [ppga2d::Point; 3],
+            opacity: f32,
+        },
+        /// Linear gradient from `start` to `end`, or radial gradient around `start` through `end`
+        /// as seen from the `focal` point, in the coordinate system of the [Shape].
+        /// The stops are offsets and straight RGBA colors in ascending order, at most [MAX_GRADIENT_STOPS].
+        Gradient {
+            radial: bool,
+            start: [f32; 2],
+            end: [f32; 2],
+            focal: [f32; 2],
+            stops: Vec<(f32, [f32; 4])>,
+        },
+    }
+
+    /// Maximum number of color stops of a [CoverPaint::Gradient]
+    pub const MAX_GRADIENT_STOPS: usize = 8;
+
+    /// Value of the stencil buffer inside of the current clip area, it is zero everywhere else
+    const CLIP_STENCIL_VALUE: i32 = 1;
+
+    /// Compares the stencil buffer to [CLIP_STENCIL_VALUE] on both faces
+    fn stencil_state(test_func: CompareFunc, pass_op: StencilOp, write_mask: u32) -> StencilState {
+        let face = StencilFaceState {
+            fail_op: StencilOp::Keep,
+            depth_fail_op: StencilOp::Keep,
+            pass_op,
+            test_func,
+            test_ref: CLIP_STENCIL_VALUE,
+            test_mask: u32::MAX,
+            write_mask,
+        };
+        StencilState {
+            front: face,
+            back: face,
+        }
+    }
+
+    /// Offscreen targets of one nesting level of matte or blend contexts
+    ///
+    /// The background is the matte of a matte context, or the backdrop of a blend context.
+    /// Both passes have a stencil buffer of their own for clipping.
+    pub struct OffscreenContext {
+        pub width: u32,
+        pub height: u32,
+        pub background_texture: TextureId,
+        pub background_pass: RenderPass,
+        pub layer_texture: TextureId,
+        pub layer_pass: RenderPass,
+        /// Of the background pass and of the layer pass
+        pub stencil_buffers: [u32; 2],
+    }
+
+    impl OffscreenContext {
+        fn new(ctx: &mut dyn RenderingBackend, width: u32, height: u32) -> OffscreenContext {
+            let mut new_texture = || {
+                ctx.new_render_texture(TextureParams {
+                    width,
+                    height,
+                    format: TextureFormat::RGBA8,
+                    ..Default::default()
+                })
+            };
+            let background_texture = new_texture();
+            let layer_texture = new_texture();
+            let (background_pass, background_stencil_buffer) =
+                new_stencil_render_pass(ctx, background_texture, width, height);
+            let (layer_pass, layer_stencil_buffer) =
+                new_stencil_render_pass(ctx, layer_texture, width, height);
+            OffscreenContext {
+                width,
+                height,
+                background_texture,
+                background_pass,
+                layer_texture,
+                layer_pass,
+                stencil_buffers: [background_stencil_buffer, layer_stencil_buffer],
+            }
+        }
+
+        fn delete(self, ctx: &mut dyn RenderingBackend) {
+            ctx.delete_render_pass(self.background_pass);
+            ctx.delete_render_pass(self.layer_pass);
+            ctx.delete_texture(self.background_texture);
+            ctx.delete_texture(self.layer_texture);
+            for stencil_buffer in self.stencil_buffers {
+                delete_stencil_buffer(stencil_buffer);
+            }
+        }
+    }
+
     pub struct Stage {
         pub fill_solid_pipeline: Pipeline,
-        pub fill_solid_bindings: Bindings,
         pub fill_integral_quadratic_curve_pipeline: Pipeline,
-        pub fill_integral_quadratic_curve_bindings: Bindings,
+        pub fill_integral_cubic_curve_pipeline: Pipeline,
         pub fill_rational_quadratic_curve_pipeline: Pipeline,
-        pub fill_rational_quadratic_curve_bindings: Bindings,
-        pub color_cover_pipeline: Pipeline,
+        pub fill_rational_cubic_curve_pipeline: Pipeline,
+        /// The fill pipelines above in the same order, but flipping the parity of the samples for even-odd
+        pub parity_fill_pipelines: [Pipeline; 5],
+        /// Adds the samples of odd parity to the coverage
+        pub parity_pipeline: Pipeline,
+        pub parity_bindings: Bindings,
+        /// Both stroke pipelines count coverage independently of the facing
+        pub stroke_line_pipeline: Pipeline,
+        pub stroke_joint_pipeline: Pipeline,
+        pub color_cover_pipelines: CoverPipelines,
+        /// Samples a texture in addition to the coverage, which is bound second
+        pub image_cover_pipelines: CoverPipelines,
+        pub gradient_cover_pipelines: CoverPipelines,
+        /// Marks the area of a [Shape] in the stencil buffer of the current target
+        pub clip_pipeline: Pipeline,
         pub color_cover_bindings: Bindings,
+        /// Composites the layer of an [OffscreenContext] through its matte
+        pub matte_pipeline: Pipeline,
+        /// Blends the layer of an [OffscreenContext] with its backdrop
+        pub blend_pipeline: Pipeline,
+        /// Replaces the target by the blend of a layer with a copy of the target, everywhere or only inside of the current clip area
+        pub backdrop_blend_pipeline: Pipeline,
+        pub clipped_backdrop_blend_pipeline: Pipeline,
+        /// The texture of the layer and the texture of the background are bound while compositing
+        pub context_bindings: Bindings,
+        /// Allocated once per nesting level and reused in every frame
+        pub offscreen_contexts: Vec<OffscreenContext>,
+        /// Passes which [Shape]s are rendered into instead of the default pass, the innermost last
+        pub targets: Vec<RenderPass>,
         pub shape2: Shape,
+        pub shape2_bindings: ShapeBindings,
     }
 
     impl Stage {
@@ -56,8 +762,7 @@ pub mod raw_miniquad {
             for path in &mut paths {
                 path.reverse();
             }
-            paths[0].stroke_options = None;
-            let shape2 = Shape::from_paths(&paths).unwrap();
+            let shape2 = Shape::from_paths(&[], &paths).unwrap();
 
             // let shape2 = Shape::from_paths(&vec![Path::from_circle([0.0, 0.0], 0.5)]).unwrap();
 
@@ -72,117 +777,102 @@ pub mod raw_miniquad {
             // let paths = vec![path];
             // let shape2 = Shape::from_paths(&paths).unwrap();
 
-            let mut fill_solid_pipeline = None;
-            let mut fill_solid_bindings = None;
-            let mut fill_integral_quadratic_curve_pipeline = None;
-            let mut fill_integral_quadratic_curve_bindings = None;
-            let mut fill_rational_quadratic_curve_pipeline = None;
-            let mut fill_rational_quadratic_curve_bindings = None;
-            for (
-                begin_offset,
-                end_offset,
-                vertex_size,
-                vertex_shader,
-                fragment_shader,
-                metal_shader,
-                attributes,
-                pipeline,
-                bindings,
-            ) in [
-                (
-                    0,
-                    shape2.vertex_offsets[0],
-                    std::mem::size_of::<Vertex0>(),
-                    shader::FILL_VERTEX,
-                    shader::FILL_FRAGMENT,
-                    shader::FILL_METAL,
-                    vec![VertexAttribute::new("position", VertexFormat::Float2)],
-                    &mut fill_solid_pipeline,
-                    &mut fill_solid_bindings,
-                ),
-                (
-                    shape2.vertex_offsets[0],
-                    shape2.vertex_offsets[1],
-                    std::mem::size_of::<Vertex2f>(),
-                    shader::INTEGRAL_QUADRATIC_VERTEX,
-                    shader::INTEGRAL_QUADRATIC_FRAGMENT,
-                    shader::INTEGRAL_QUADRATIC_METAL,
-                    vec![
-                        VertexAttribute::new("position", VertexFormat::Float2),
-                        VertexAttribute::new("in_weights", VertexFormat::Float2),
-                    ],
-                    &mut fill_integral_quadratic_curve_pipeline,
-                    &mut fill_integral_quadratic_curve_bindings,
-                ),
-                (
-                    shape2.vertex_offsets[2],
-                    shape2.vertex_offsets[3],
-                    std::mem::size_of::<Vertex3f>(),
-                    shader::QUADRATIC_VERTEX,
-                    shader::QUADRATIC_FRAGMENT,
-                    shader::QUADRATIC_METAL,
-                    vec![
-                        VertexAttribute::new("position", VertexFormat::Float2),
-                        VertexAttribute::new("in_weights", VertexFormat::Float3),
-                    ],
-                    &mut fill_rational_quadratic_curve_pipeline,
-                    &mut fill_rational_quadratic_curve_bindings,
-                ),
-            ] {
-                let vertices = &shape2.vertex_buffer[begin_offset..end_offset];
-                let vertex_buffer = ctx.new_buffer(
-                    BufferType::VertexBuffer,
-                    BufferUsage::Immutable,
-                    BufferSource::slice(&vertices),
-                );
-                let indices: Vec<u16> =
-                    (0..((end_offset - begin_offset) / vertex_size) as u16).collect();
-
-                let index_buffer = ctx.new_buffer(
-                    BufferType::IndexBuffer,
-                    BufferUsage::Immutable,
-                    BufferSource::slice(&indices),
-                );
+            let shape2_bindings = ShapeBindings::new(ctx, &shape2);
 
-                *bindings = Some(Bindings {
-                    vertex_buffers: vec![vertex_buffer],
-                    index_buffer,
-                    images: vec![],
-                });
-
-                let shader = ctx
-                    .new_shader(
-                        match ctx.info().backend {
-                            Backend::OpenGl => ShaderSource::Glsl {
-                                vertex: vertex_shader,
-                                fragment: fragment_shader,
-                            },
-                            Backend::Metal => ShaderSource::Msl {
-                                program: metal_shader,
-                            },
-                        },
+            let mut new_fill_pipelines = |parity: bool| {
+                [
+                    new_fill_pipeline(
+                        ctx,
+                        shader::FILL_VERTEX,
+                        shader::FILL_FRAGMENT,
+                        shader::FILL_METAL,
                         shader::meta(),
-                    )
-                    .unwrap();
-
-                *pipeline = Some(ctx.new_pipeline(
-                    &[BufferLayout::default()],
-                    &attributes,
-                    shader,
-                    PipelineParams {
-                        primitive_type: PrimitiveType::Triangles,
-                        color_blend: Some(BlendState::new(
-                            Equation::Add,
-                            BlendFactor::One,
-                            BlendFactor::One,
-                        )),
-                        ..Default::default()
-                    },
-                ));
-            }
+                        &[VertexAttribute::new("position", VertexFormat::Float2)],
+                        parity,
+                    ),
+                    new_fill_pipeline(
+                        ctx,
+                        shader::INTEGRAL_QUADRATIC_VERTEX,
+                        shader::INTEGRAL_QUADRATIC_FRAGMENT,
+                        shader::INTEGRAL_QUADRATIC_METAL,
+                        shader::meta(),
+                        &[
+                            VertexAttribute::new("position", VertexFormat::Float2),
+                            VertexAttribute::new("in_weights", VertexFormat::Float2),
+                        ],
+                        parity,
+                    ),
+                    new_fill_pipeline(
+                        ctx,
+                        shader::INTEGRAL_CUBIC_VERTEX,
+                        shader::INTEGRAL_CUBIC_FRAGMENT,
+                        shader::INTEGRAL_CUBIC_METAL,
+                        shader::meta(),
+                        &[
+                            VertexAttribute::new("position", VertexFormat::Float2),
+                            VertexAttribute::new("in_weights", VertexFormat::Float3),
+                        ],
+                        parity,
+                    ),
+                    new_fill_pipeline(
+                        ctx,
+                        shader::QUADRATIC_VERTEX,
+                        shader::QUADRATIC_FRAGMENT,
+                        shader::QUADRATIC_METAL,
+                        shader::meta(),
+                        &[
+                            VertexAttribute::new("position", VertexFormat::Float2),
+                            VertexAttribute::new("in_weights", VertexFormat::Float3),
+                        ],
+                        parity,
+                    ),
+                    new_fill_pipeline(
+                        ctx,
+                        shader::RATIONAL_CUBIC_VERTEX,
+                        shader::RATIONAL_CUBIC_FRAGMENT,
+                        shader::RATIONAL_CUBIC_METAL,
+                        shader::meta(),
+                        &[
+                            VertexAttribute::new("position", VertexFormat::Float2),
+                            VertexAttribute::new("in_weights", VertexFormat::Float4),
+                        ],
+                        parity,
+                    ),
+                ]
+            };
+            let fill_pipelines = new_fill_pipelines(false);
+            // Even-odd flips the parity of each sample in the stencil buffer with the same shaders instead
+            let parity_fill_pipelines = new_fill_pipelines(true);
+            // The path index is unpacked from its bytes, as GLSL 100 has no integer attributes
+            let stroke_line_pipeline = new_fill_pipeline(
+                ctx,
+                shader::STROKE_LINE_VERTEX,
+                shader::STROKE_LINE_FRAGMENT,
+                shader::STROKE_LINE_METAL,
+                shader::stroke_meta(),
+                &[
+                    VertexAttribute::new("position", VertexFormat::Float2),
+                    VertexAttribute::new("in_weights", VertexFormat::Float2),
+                    VertexAttribute::new("in_path", VertexFormat::Byte4),
+                ],
+                false,
+            );
+            let stroke_joint_pipeline = new_fill_pipeline(
+                ctx,
+                shader::STROKE_JOINT_VERTEX,
+                shader::STROKE_JOINT_FRAGMENT,
+                shader::STROKE_JOINT_METAL,
+                shader::stroke_meta(),
+                &[
+                    VertexAttribute::new("position", VertexFormat::Float2),
+                    VertexAttribute::new("in_weights", VertexFormat::Float3),
+                    VertexAttribute::new("in_path", VertexFormat::Byte4),
+                ],
+                false,
+            );
 
-            let begin_offset: usize = shape2.vertex_offsets[5];
-            let end_offset = shape2.vertex_offsets[6];
+            let begin_offset: usize = shape2.vertex_offsets[7];
+            let end_offset = shape2.vertex_offsets[8];
             let vertices = &shape2.vertex_buffer[begin_offset..end_offset];
             let vertex_buffer = ctx.new_buffer(
                 BufferType::VertexBuffer,
@@ -211,101 +901,1774 @@ pub mod raw_miniquad {
                 index_buffer,
                 images: vec![color_img],
             };
-            let color_cover_shader = ctx
-                .new_shader(
-                    match ctx.info().backend {
-                        Backend::OpenGl => ShaderSource::Glsl {
-                            vertex: shader::COVER_VERTEX,
-                            fragment: shader::COVER_FRAGMENT,
-                        },
-                        Backend::Metal => ShaderSource::Msl {
-                            program: shader::COVER_METAL,
-                        },
-                    },
-                    shader::cover_meta(),
-                )
-                .unwrap();
-            let color_cover_pipeline = ctx.new_pipeline(
-                &[BufferLayout::default()],
-                &[
-                    VertexAttribute::new("position", VertexFormat::Float2),
-                    VertexAttribute::new("in_uv", VertexFormat::Float2),
-                ],
-                color_cover_shader,
+            let context_bindings = Bindings {
+                vertex_buffers: vec![vertex_buffer],
+                index_buffer,
+                images: vec![color_img, color_img],
+            };
+            let parity_bindings = Bindings {
+                vertex_buffers: vec![vertex_buffer],
+                index_buffer,
+                images: vec![],
+            };
+            // Counts the samples of odd parity as covered once and resets their parity
+            let parity_pipeline = new_cover_pipeline(
+                ctx,
+                shader::PARITY_FRAGMENT,
+                shader::PARITY_METAL,
+                shader::parity_meta(),
+                PipelineParams {
+                    primitive_type: PrimitiveType::Triangles,
+                    color_blend: Some(BlendState::new(
+                        Equation::Add,
+                        BlendFactor::One,
+                        BlendFactor::One,
+                    )),
+                    stencil_test: Some(stencil_state(CompareFunc::Equal, StencilOp::Zero, 1)),
+                    ..Default::default()
+                },
+            );
+            let color_cover_pipelines = CoverPipelines::new(
+                ctx,
+                shader::COVER_FRAGMENT,
+                shader::COVER_METAL,
+                shader::cover_meta,
+            );
+            let image_cover_pipelines = CoverPipelines::new(
+                ctx,
+                shader::IMAGE_FRAGMENT,
+                shader::IMAGE_METAL,
+                shader::image_meta,
+            );
+            let gradient_cover_pipelines = CoverPipelines::new(
+                ctx,
+                shader::GRADIENT_FRAGMENT,
+                shader::GRADIENT_METAL,
+                shader::gradient_meta,
+            );
+            let clip_pipeline = new_cover_pipeline(
+                ctx,
+                shader::CLIP_FRAGMENT,
+                shader::CLIP_METAL,
+                shader::cover_meta(),
+                PipelineParams {
+                    primitive_type: PrimitiveType::Triangles,
+                    color_write: (false, false, false, false),
+                    stencil_test: Some(stencil_state(
+                        CompareFunc::Always,
+                        StencilOp::Replace,
+                        u32::MAX,
+                    )),
+                    ..Default::default()
+                },
+            );
+
+            // Both composite premultiplied colors onto the enclosing target
+            let matte_pipeline = new_cover_pipeline(
+                ctx,
+                shader::MATTE_FRAGMENT,
+                shader::MATTE_METAL,
+                shader::context_meta("matte"),
+                PipelineParams {
+                    primitive_type: PrimitiveType::Triangles,
+                    color_blend: Some(blend_state(Blending::Normal)),
+                    ..Default::default()
+                },
+            );
+            let blend_pipeline = new_cover_pipeline(
+                ctx,
+                shader::BLEND_FRAGMENT,
+                shader::BLEND_METAL,
+                shader::context_meta("backdrop"),
                 PipelineParams {
                     primitive_type: PrimitiveType::Triangles,
-                    // color_blend: Some(BlendState::new(
-                    //     Equation::Add,
-                    //     BlendFactor::Zero,
-                    //     BlendFactor::Value(BlendValue::SourceColor),
-                    // )),
+                    color_blend: Some(blend_state(Blending::Normal)),
                     ..Default::default()
                 },
             );
+            // These write the blended result as is, because the backdrop is a copy of the target
+            let mut new_backdrop_blend_pipeline = |stencil_test: Option<StencilState>| {
+                new_cover_pipeline(
+                    ctx,
+                    shader::BLEND_FRAGMENT,
+                    shader::BLEND_METAL,
+                    shader::context_meta("backdrop"),
+                    PipelineParams {
+                        primitive_type: PrimitiveType::Triangles,
+                        stencil_test,
+                        ..Default::default()
+                    },
+                )
+            };
+            let backdrop_blend_pipeline = new_backdrop_blend_pipeline(None);
+            let clipped_backdrop_blend_pipeline = new_backdrop_blend_pipeline(Some(stencil_state(
+                CompareFunc::Equal,
+                StencilOp::Keep,
+                0,
+            )));
 
-            let fill_solid_pipeline = fill_solid_pipeline.unwrap();
-            let fill_solid_bindings = fill_solid_bindings.unwrap();
-            let fill_integral_quadratic_curve_pipeline =
-                fill_integral_quadratic_curve_pipeline.unwrap();
-            let fill_integral_quadratic_curve_bindings =
-                fill_integral_quadratic_curve_bindings.unwrap();
-            let fill_rational_quadratic_curve_pipeline =
-                fill_rational_quadratic_curve_pipeline.unwrap();
-            let fill_rational_quadratic_curve_bindings =
-                fill_rational_quadratic_curve_bindings.unwrap();
             Stage {
-                fill_solid_pipeline,
-                fill_solid_bindings,
-                fill_integral_quadratic_curve_pipeline,
-                fill_integral_quadratic_curve_bindings,
-                fill_rational_quadratic_curve_pipeline,
-                fill_rational_quadratic_curve_bindings,
-                color_cover_pipeline,
+                fill_solid_pipeline: fill_pipelines[0],
+                fill_integral_quadratic_curve_pipeline: fill_pipelines[1],
+                fill_integral_cubic_curve_pipeline: fill_pipelines[2],
+                fill_rational_quadratic_curve_pipeline: fill_pipelines[3],
+                fill_rational_cubic_curve_pipeline: fill_pipelines[4],
+                parity_fill_pipelines,
+                parity_pipeline,
+                parity_bindings,
+                stroke_line_pipeline,
+                stroke_joint_pipeline,
+                color_cover_pipelines,
+                image_cover_pipelines,
+                gradient_cover_pipelines,
+                clip_pipeline,
                 color_cover_bindings,
+                matte_pipeline,
+                blend_pipeline,
+                backdrop_blend_pipeline,
+                clipped_backdrop_blend_pipeline,
+                context_bindings,
+                offscreen_contexts: Vec::new(),
+                targets: Vec::new(),
                 shape2,
+                shape2_bindings,
             }
         }
-    }
 
-    pub mod shader {
-        use macroquad::miniquad::*;
+        /// Renders a [Shape] with anti-aliasing on top of the current content of the current target.
+        ///
+        /// The coverage is accumulated in the `offscreen_pass` (whose texture has to be bound
+        /// in `color_cover_bindings`) by rendering the [Shape] once per [JITTER_PATTERN] sample.
+        /// Then the cover pass resolves the coverage and blends the `paint` onto the screen
+        /// using a fixed-function `blending`, only inside of the current clip area if `clipped` is set.
+        /// Inside of offscreen contexts Multiply and Screen are blended with a copy of the target instead.
+        /// With the even-odd `fill_rule` the fills only flip the parity of each sample in the stencil buffer
+        /// of the `offscreen_pass`, then every sample of odd parity counts as covered once.
+        /// If the alpha of a [CoverPaint::Color] is zero, the coverage is inverted instead (black on white).
+        #[allow(clippy::too_many_arguments)]
+        pub fn draw_shape(
+            &mut self,
+            ctx: &mut dyn RenderingBackend,
+            offscreen_pass: RenderPass,
+            shape_bindings: &ShapeBindings,
+            projection_matrix: &[ppga3d::Point; 4],
+            model_matrix: &[ppga3d::Point; 4],
+            paint: &CoverPaint,
+            fill_rule: FillRule,
+            blending: Blending,
+            clipped: bool,
+        ) {
+            if !self.targets.is_empty() && matches!(blending, Blending::Multiply | Blending::Screen)
+            {
+                self.draw_shape_through_backdrop(
+                    ctx,
+                    offscreen_pass,
+                    shape_bindings,
+                    projection_matrix,
+                    model_matrix,
+                    paint,
+                    fill_rule,
+                    blending,
+                    clipped,
+                );
+                return;
+            }
+            self.accumulate_coverage(
+                ctx,
+                offscreen_pass,
+                shape_bindings,
+                projection_matrix,
+                model_matrix,
+                fill_rule,
+            );
+            let pipelines = match paint {
+                CoverPaint::Color(_) => &self.color_cover_pipelines,
+                CoverPaint::Image { .. } => &self.image_cover_pipelines,
+                CoverPaint::Gradient { .. } => &self.gradient_cover_pipelines,
+            };
+            self.cover(
+                ctx,
+                pipelines.get(blending, clipped),
+                PassAction::Nothing,
+                shape_bindings,
+                projection_matrix,
+                model_matrix,
+                paint,
+            );
+        }
+
+        /// Blends a [Shape] with the content of the current offscreen target like a blend context does.
+        ///
+        /// The [OffscreenContext] of the next nesting level serves as scratch space:
+        /// Its background receives a copy of the target and its layer the [Shape],
+        /// then their blend replaces the target, only inside of the current clip area if `clipped` is set.
+        #[allow(clippy::too_many_arguments)]
+        fn draw_shape_through_backdrop(
+            &mut self,
+            ctx: &mut dyn RenderingBackend,
+            offscreen_pass: RenderPass,
+            shape_bindings: &ShapeBindings,
+            projection_matrix: &[ppga3d::Point; 4],
+            model_matrix: &[ppga3d::Point; 4],
+            paint: &CoverPaint,
+            fill_rule: FillRule,
+            blending: Blending,
+            clipped: bool,
+        ) {
+            let depth = self.targets.len();
+            let target = self.targets[depth - 1];
+            let enclosing_context = &self.offscreen_contexts[depth - 1];
+            let target_texture = if target == enclosing_context.background_pass {
+                enclosing_context.background_texture
+            } else {
+                enclosing_context.layer_texture
+            };
+            self.allocate_offscreen_context(ctx, depth);
+            let context = &self.offscreen_contexts[depth];
+            let (background_pass, background_texture) =
+                (context.background_pass, context.background_texture);
+            let (layer_pass, layer_texture) = (context.layer_pass, context.layer_texture);
+            ctx.begin_pass(
+                Some(layer_pass),
+                PassAction::Clear {
+                    color: Some((0.0, 0.0, 0.0, 0.0)),
+                    depth: None,
+                    stencil: Some(0),
+                },
+            );
+            ctx.end_render_pass();
+            // Blending the empty layer reproduces its backdrop, which copies the target
+            self.composite(
+                ctx,
+                background_pass,
+                self.backdrop_blend_pipeline,
+                [layer_texture, target_texture],
+                Blending::Normal,
+            );
+            self.targets.push(layer_pass);
+            self.draw_shape(
+                ctx,
+                offscreen_pass,
+                shape_bindings,
+                projection_matrix,
+                model_matrix,
+                paint,
+                fill_rule,
+                Blending::Normal,
+                false,
+            );
+            self.targets.pop();
+            self.composite(
+                ctx,
+                target,
+                if clipped {
+                    self.clipped_backdrop_blend_pipeline
+                } else {
+                    self.backdrop_blend_pipeline
+                },
+                [layer_texture, background_texture],
+                blending,
+            );
+        }
+
+        /// Blends the texture of a `layer` with the texture of a `backdrop` into the `pass`
+        fn composite(
+            &mut self,
+            ctx: &mut dyn RenderingBackend,
+            pass: RenderPass,
+            pipeline: Pipeline,
+            [layer, backdrop]: [TextureId; 2],
+            blending: Blending,
+        ) {
+            self.context_bindings.images = vec![layer, backdrop];
+            ctx.begin_pass(Some(pass), PassAction::Nothing);
+            ctx.apply_pipeline(&pipeline);
+            ctx.apply_bindings(&self.context_bindings);
+            ctx.apply_uniforms(UniformsSource::table(&shader::ContextUniforms {
+                in_mode: blend_mode(blending),
+            }));
+            ctx.draw(0, 6, 1);
+            ctx.end_render_pass();
+        }
+
+        /// Replaces the current clip area by the area of a [Shape].
+        ///
+        /// The coverage is accumulated like in [Stage::draw_shape],
+        /// then pixels which are covered at least by half are marked in the stencil buffer of the current target.
+        pub fn clip_shape(
+            &self,
+            ctx: &mut dyn RenderingBackend,
+            offscreen_pass: RenderPass,
+            shape_bindings: &ShapeBindings,
+            projection_matrix: &[ppga3d::Point; 4],
+            model_matrix: &[ppga3d::Point; 4],
+            fill_rule: FillRule,
+        ) {
+            self.accumulate_coverage(
+                ctx,
+                offscreen_pass,
+                shape_bindings,
+                projection_matrix,
+                model_matrix,
+                fill_rule,
+            );
+            self.cover(
+                ctx,
+                &self.clip_pipeline,
+                PassAction::Clear {
+                    color: None,
+                    depth: None,
+                    stencil: Some(0),
+                },
+                shape_bindings,
+                projection_matrix,
+                model_matrix,
+                &CoverPaint::Color([1.0; 4]),
+            );
+        }
+
+        /// Removes the current clip area, so that the following [Shape]s are rendered everywhere again
+        pub fn unclip(&self, ctx: &mut dyn RenderingBackend) {
+            self.begin_target_pass(
+                ctx,
+                PassAction::Clear {
+                    color: None,
+                    depth: None,
+                    stencil: Some(0),
+                },
+            );
+            ctx.end_render_pass();
+        }
+
+        fn accumulate_coverage(
+            &self,
+            ctx: &mut dyn RenderingBackend,
+            offscreen_pass: RenderPass,
+            shape_bindings: &ShapeBindings,
+            projection_matrix: &[ppga3d::Point; 4],
+            model_matrix: &[ppga3d::Point; 4],
+            fill_rule: FillRule,
+        ) {
+            ctx.begin_pass(
+                Some(offscreen_pass),
+                PassAction::Clear {
+                    color: Some((0.0, 0.0, 0.0, 0.0)),
+                    depth: None,
+                    stencil: Some(0),
+                },
+            );
+            let (screen_width, screen_height) = window::screen_size();
+            let scale = 1. / window::dpi_scale();
+            let even_odd = fill_rule == FillRule::EvenOdd;
+            for (j, offset) in JITTER_PATTERN.iter().enumerate() {
+                let mut in_color = [1.0; 4];
+                if j % 2 == 0 {
+                    in_color[0] = if j == 0 { 1.0 } else { 0.0 };
+                    in_color[1] = if j == 2 { 1.0 } else { 0.0 };
+                    in_color[2] = if j == 4 { 1.0 } else { 0.0 };
+                }
+                let jitter_matrix = matrix_multiplication(
+                    &matrix_multiplication(
+                        projection_matrix,
+                        &motor3d_to_mat4(
+                            &Translator::new(
+                                1.0,
+                                (offset[0] * scale) / screen_width,
+                                (offset[1] * scale) / screen_height,
+                                0.0,
+                            )
+                            .geometric_product(Rotor::one()),
+                        ),
+                    ),
+                    model_matrix,
+                );
+                for (i, (pipeline, bindings, count)) in [
+                    (
+                        &self.fill_solid_pipeline,
+                        &shape_bindings.fill_solid_bindings,
+                        shape_bindings.fill_solid_count,
+                    ),
+                    (
+                        &self.fill_integral_quadratic_curve_pipeline,
+                        &shape_bindings.fill_integral_quadratic_curve_bindings,
+                        shape_bindings.fill_integral_quadratic_curve_count,
+                    ),
+                    (
+                        &self.fill_integral_cubic_curve_pipeline,
+                        &shape_bindings.fill_integral_cubic_curve_bindings,
+                        shape_bindings.fill_integral_cubic_curve_count,
+                    ),
+                    (
+                        &self.fill_rational_quadratic_curve_pipeline,
+                        &shape_bindings.fill_rational_quadratic_curve_bindings,
+                        shape_bindings.fill_rational_quadratic_curve_count,
+                    ),
+                    (
+                        &self.fill_rational_cubic_curve_pipeline,
+                        &shape_bindings.fill_rational_cubic_curve_bindings,
+                        shape_bindings.fill_rational_cubic_curve_count,
+                    ),
+                ]
+                .into_iter()
+                .enumerate()
+                {
+                    if count == 0 {
+                        continue;
+                    }
+                    ctx.apply_pipeline(if even_odd {
+                        &self.parity_fill_pipelines[i]
+                    } else {
+                        pipeline
+                    });
+                    ctx.apply_bindings(bindings);
+                    ctx.apply_uniforms(UniformsSource::table(&shader::Uniforms {
+                        transform_row_0: jitter_matrix[0].into(),
+                        transform_row_1: jitter_matrix[1].into(),
+                        transform_row_2: jitter_matrix[2].into(),
+                        transform_row_3: jitter_matrix[3].into(),
+                        in_color,
+                    }));
+                    ctx.draw(0, count, 1);
+                }
+                if even_odd {
+                    ctx.apply_pipeline(&self.parity_pipeline);
+                    ctx.apply_bindings(&self.parity_bindings);
+                    ctx.apply_uniforms(UniformsSource::table(&shader::ParityUniforms { in_color }));
+                    ctx.draw(0, 6, 1);
+                }
+                for (pipeline, bindings, count) in [
+                    (
+                        &self.stroke_line_pipeline,
+                        &shape_bindings.stroke_line_bindings,
+                        shape_bindings.stroke_line_count,
+                    ),
+                    (
+                        &self.stroke_joint_pipeline,
+                        &shape_bindings.stroke_joint_bindings,
+                        shape_bindings.stroke_joint_count,
+                    ),
+                ] {
+                    if count == 0 {
+                        continue;
+                    }
+                    ctx.apply_pipeline(pipeline);
+                    ctx.apply_bindings(bindings);
+                    ctx.apply_uniforms(UniformsSource::table(&shader::StrokeUniforms {
+                        transform_row_0: jitter_matrix[0].into(),
+                        transform_row_1: jitter_matrix[1].into(),
+                        transform_row_2: jitter_matrix[2].into(),
+                        transform_row_3: jitter_matrix[3].into(),
+                        in_color,
+                        in_stroke_options: shape_bindings.stroke_options,
+                    }));
+                    ctx.draw(0, count, 1);
+                }
+            }
+            ctx.end_render_pass();
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        fn cover(
+            &self,
+            ctx: &mut dyn RenderingBackend,
+            pipeline: &Pipeline,
+            pass_action: PassAction,
+            shape_bindings: &ShapeBindings,
+            projection_matrix: &[ppga3d::Point; 4],
+            model_matrix: &[ppga3d::Point; 4],
+            paint: &CoverPaint,
+        ) {
+            // The plane z = 0 maps to normalized device coordinates by a homography,
+            // built from the columns x, y and w and the rows x, y and w of the 4x4 matrix
+            let transform = matrix_multiplication(projection_matrix, model_matrix);
+            let homography = [0, 1, 3].map(|column| {
+                ppga2d::Point::new(
+                    transform[column][0],
+                    transform[column][1],
+                    transform[column][3],
+                )
+            });
+            self.begin_target_pass(ctx, pass_action);
+            ctx.apply_pipeline(pipeline);
+            match paint {
+                CoverPaint::Color(color) => {
+                    ctx.apply_bindings(&self.color_cover_bindings);
+                    ctx.apply_uniforms(UniformsSource::table(&shader::CoverUniforms {
+                        transform_row_0: projection_matrix[0].into(),
+                        transform_row_1: projection_matrix[1].into(),
+                        transform_row_2: projection_matrix[2].into(),
+                        transform_row_3: projection_matrix[3].into(),
+                        in_color: *color,
+                        in_rect: [
+                            shape_bindings.convex_box[0].floor(),
+                            1. - shape_bindings.convex_box[3].ceil(),
+                            shape_bindings.convex_box[2].ceil(),
+                            1. - shape_bindings.convex_box[1].floor(),
+                        ],
+                    }));
+                }
+                CoverPaint::Image {
+                    texture,
+                    matrix,
+                    opacity,
+                } => {
+                    let Some(uv_matrix) = mat3_inverse(&mat3_multiplication(&homography, matrix))
+                    else {
+                        ctx.end_render_pass();
+                        return;
+                    };
+                    let mut bindings = self.color_cover_bindings.clone();
+                    bindings.images.push(*texture);
+                    ctx.apply_bindings(&bindings);
+                    ctx.apply_uniforms(UniformsSource::table(&shader::ImageUniforms {
+                        uv_column_0: [uv_matrix[0][0], uv_matrix[0][1], uv_matrix[0][2], 0.0],
+                        uv_column_1: [uv_matrix[1][0], uv_matrix[1][1], uv_matrix[1][2], 0.0],
+                        uv_column_2: [uv_matrix[2][0], uv_matrix[2][1], uv_matrix[2][2], 0.0],
+                        in_opacity: [*opacity, 0.0, 0.0, 0.0],
+                    }));
+                }
+                CoverPaint::Gradient {
+                    radial,
+                    start,
+                    end,
+                    focal,
+                    stops,
+                } => {
+                    // Maps back to the coordinate system of the gradient
+                    let Some(uv_matrix) = mat3_inverse(&homography) else {
+                        ctx.end_render_pass();
+                        return;
+                    };
+                    let stops = &stops[..stops.len().min(MAX_GRADIENT_STOPS)];
+                    let mut in_stop_offsets = [[0.0; 4]; MAX_GRADIENT_STOPS];
+                    let mut in_stop_colors = [[0.0; 4]; MAX_GRADIENT_STOPS];
+                    for (i, (offset, color)) in stops.iter().enumerate() {
+                        in_stop_offsets[i][0] = *offset;
+                        in_stop_colors[i] = *color;
+                    }
+                    ctx.apply_bindings(&self.color_cover_bindings);
+                    ctx.apply_uniforms(UniformsSource::table(&shader::GradientUniforms {
+                        uv_column_0: [uv_matrix[0][0], uv_matrix[0][1], uv_matrix[0][2], 0.0],
+                        uv_column_1: [uv_matrix[1][0], uv_matrix[1][1], uv_matrix[1][2], 0.0],
+                        uv_column_2: [uv_matrix[2][0], uv_matrix[2][1], uv_matrix[2][2], 0.0],
+                        in_gradient: [
+                            if *radial { 1.0 } else { 0.0 },
+                            stops.len() as f32,
+                            0.0,
+                            0.0,
+                        ],
+                        in_start_end: [start[0], start[1], end[0], end[1]],
+                        in_focal: [focal[0], focal[1], 0.0, 0.0],
+                        in_stop_offsets,
+                        in_stop_colors,
+                    }));
+                }
+            }
+            ctx.draw(0, 6, 1);
+            ctx.end_render_pass();
+        }
+
+        /// Begins the innermost target pass, or the default pass outside of offscreen contexts
+        fn begin_target_pass(&self, ctx: &mut dyn RenderingBackend, pass_action: PassAction) {
+            match self.targets.last() {
+                Some(target) => ctx.begin_pass(Some(*target), pass_action),
+                None => ctx.begin_default_pass(pass_action),
+            }
+        }
+
+        /// Redirects the following [Shape]s into the background of a new (possibly nested) [OffscreenContext].
+        ///
+        /// The context starts without a clip area, the enclosing one is kept by the enclosing target.
+        pub fn save_offscreen_context(&mut self, ctx: &mut dyn RenderingBackend) {
+            let depth = self.targets.len();
+            self.allocate_offscreen_context(ctx, depth);
+            let background_pass = self.offscreen_contexts[depth].background_pass;
+            ctx.begin_pass(
+                Some(background_pass),
+                PassAction::Clear {
+                    color: Some((0.0, 0.0, 0.0, 0.0)),
+                    depth: None,
+                    stencil: Some(0),
+                },
+            );
+            ctx.end_render_pass();
+            self.targets.push(background_pass);
+        }
+
+        /// Makes sure that the [OffscreenContext] of a nesting level exists and matches the screen size
+        fn allocate_offscreen_context(&mut self, ctx: &mut dyn RenderingBackend, depth: usize) {
+            let (width, height) = window::screen_size();
+            let (width, height) = (width as u32, height as u32);
+            match self.offscreen_contexts.get(depth) {
+                Some(context) if context.width == width && context.height == height => {}
+                Some(_) => {
+                    let context = std::mem::replace(
+                        &mut self.offscreen_contexts[depth],
+                        OffscreenContext::new(ctx, width, height),
+                    );
+                    context.delete(ctx);
+                }
+                None => self
+                    .offscreen_contexts
+                    .push(OffscreenContext::new(ctx, width, height)),
+            }
+        }
+
+        /// Redirects the following [Shape]s into the layer of the innermost [OffscreenContext], without a clip area
+        pub fn apply_offscreen_context(&mut self, ctx: &mut dyn RenderingBackend) {
+            let layer_pass = self.offscreen_contexts[self.targets.len() - 1].layer_pass;
+            ctx.begin_pass(
+                Some(layer_pass),
+                PassAction::Clear {
+                    color: Some((0.0, 0.0, 0.0, 0.0)),
+                    depth: None,
+                    stencil: Some(0),
+                },
+            );
+            ctx.end_render_pass();
+            *self.targets.last_mut().unwrap() = layer_pass;
+        }
+
+        /// Leaves the innermost [OffscreenContext] of a matte and composites its layer onto the enclosing target.
+        ///
+        /// The layer is scaled by the alpha of the matte, or its luminance if `luma` is set.
+        pub fn restore_matte_context(
+            &mut self,
+            ctx: &mut dyn RenderingBackend,
+            luma: bool,
+            inverted: bool,
+        ) {
+            let in_mode = [
+                if luma { 1.0 } else { 0.0 },
+                if inverted { 1.0 } else { 0.0 },
+                0.0,
+                0.0,
+            ];
+            self.restore_offscreen_context(ctx, self.matte_pipeline, in_mode);
+        }
+
+        /// Leaves the innermost [OffscreenContext], blends its layer with its backdrop
+        /// and composites the result onto the enclosing target.
+        ///
+        /// The backdrop already contains everything beneath the layer, so the result replaces it.
+        pub fn restore_blend_context(
+            &mut self,
+            ctx: &mut dyn RenderingBackend,
+            blending: Blending,
+        ) {
+            self.restore_offscreen_context(ctx, self.blend_pipeline, blend_mode(blending));
+        }
+
+        fn restore_offscreen_context(
+            &mut self,
+            ctx: &mut dyn RenderingBackend,
+            pipeline: Pipeline,
+            in_mode: [f32; 4],
+        ) {
+            self.targets.pop();
+            let context = &self.offscreen_contexts[self.targets.len()];
+            self.context_bindings.images = vec![context.layer_texture, context.background_texture];
+            self.begin_target_pass(ctx, PassAction::Nothing);
+            ctx.apply_pipeline(&pipeline);
+            ctx.apply_bindings(&self.context_bindings);
+            ctx.apply_uniforms(UniformsSource::table(&shader::ContextUniforms { in_mode }));
+            ctx.draw(0, 6, 1);
+            ctx.end_render_pass();
+        }
+    }
+
+    pub mod shader {
+        use macroquad::miniquad::*;
+
+        pub const FILL_VERTEX: &str = r#"#version 100
+precision highp float;
+
+uniform vec4 transform_row_0;
+uniform vec4 transform_row_1;
+uniform vec4 transform_row_2;
+uniform vec4 transform_row_3;
+
+attribute vec2 position;
+
+void main() {
+    mat4 instance = mat4(transform_row_0, transform_row_1,
+                         transform_row_2, transform_row_3);
+    gl_Position = instance * vec4(position, 0.0, 1.0);
+}
+"#;
+
+        pub const FILL_FRAGMENT: &str = r#"#version 100
+precision highp float;
+uniform vec4 in_color;
+
+void main() {
+    gl_FragColor = in_color * (gl_FrontFacing ? 16.0 / 255.0 : 1.0 / 255.0);
+}"#;
+
+        pub const FILL_METAL: &str = r#"
+    #include <metal_stdlib>
+
+    using namespace metal;
+
+    struct Uniforms
+    {
+        float4 transform_row_0;
+        float4 transform_row_1;
+        float4 transform_row_2;
+        float4 transform_row_3;
+    };
+
+    struct Vertex
+    {
+        float2 position      [[attribute(0)]];
+    };
+
+    struct RasterizerData
+    {
+        float4 position [[position]];
+    };
+
+    vertex RasterizerData vertexShader(Vertex v [[stage_in]], constant Uniforms& uniforms [[buffer(0)]])
+    {
+        RasterizerData out;
+
+        float4x4 instance = float4x4(uniforms.transform_row_0,
+                                     uniforms.transform_row_1,
+                                     uniforms.transform_row_2,
+                                     uniforms.transform_row_3);
+        out.position = instance * float4(v.position, 0.0, 1.0);
+
+        return out;
+    }
+
+    fragment float4 fragmentShader(RasterizerData in [[stage_in]])
+    {
+        return float4(0.1, 0.5, 0.2, 1.0);
+    }
+"#;
+
+        pub const QUADRATIC_VERTEX: &str = r#"#version 100
+precision highp float;
+
+uniform vec4 transform_row_0;
+uniform vec4 transform_row_1;
+uniform vec4 transform_row_2;
+uniform vec4 transform_row_3;
+
+attribute vec2 position;
+attribute vec3 in_weights;
+
+varying vec3 weights;
+
+void main() {
+    mat4 instance = mat4(transform_row_0, transform_row_1,
+                         transform_row_2, transform_row_3);
+    gl_Position = instance * vec4(position, 0.0, 1.0);
+    weights = in_weights;
+}
+"#;
+
+        pub const QUADRATIC_FRAGMENT: &str = r#"#version 100
+precision highp float;
+
+varying vec3 weights;
+uniform vec4 in_color;
+
+void main() {
+    if ((weights.x * weights.x - weights.y * weights.z) <= 0.0)
+        gl_FragColor = in_color * (gl_FrontFacing ? 16.0 / 255.0 : 1.0 / 255.0);
+    else
+        discard;
+}"#;
+
+        pub const QUADRATIC_METAL: &str = r#"
+    #include <metal_stdlib>
+
+    using namespace metal;
+
+    struct Uniforms
+    {
+        float4 transform_row_0;
+        float4 transform_row_1;
+        float4 transform_row_2;
+        float4 transform_row_3;
+        float4 in_color;
+    };
+
+    struct Vertex
+    {
+        float2 position      [[attribute(0)]];
+        float3 in_weights    [[attribute(1)]];
+    };
+
+    struct RasterizerData
+    {
+        float4 position [[position]];
+        float3 weights [[user(locn0)]];
+    };
+
+    vertex RasterizerData vertexShader(Vertex v [[stage_in]], constant Uniforms& uniforms [[buffer(0)]])
+    {
+        RasterizerData out;
+
+        float4x4 instance = float4x4(uniforms.transform_row_0,
+                                     uniforms.transform_row_1,
+                                     uniforms.transform_row_2,
+                                     uniforms.transform_row_3);
+        out.position = instance * float4(v.position, 0.0, 1.0);
+        out.weights = v.in_weights;
+
+        return out;
+    }
+
+    float4 coverage(bool keep) {
+        return keep ? float4(0.1, 0.5, 0.2, 1.0) : float4(0.0);
+    }
+
+    fragment float4 fragmentShader(RasterizerData in [[stage_in]])
+    {
+        return coverage((in.weights.x * in.weights.x - in.weights.y * in.weights.z) <= 0.0);
+    }
+"#;
+
+        pub const INTEGRAL_QUADRATIC_VERTEX: &str = r#"#version 100
+precision highp float;
+
+uniform vec4 transform_row_0;
+uniform vec4 transform_row_1;
+uniform vec4 transform_row_2;
+uniform vec4 transform_row_3;
+
+attribute vec2 position;
+attribute vec2 in_weights;
+
+varying vec2 weights;
+
+void main() {
+    mat4 instance = mat4(transform_row_0, transform_row_1,
+                         transform_row_2, transform_row_3);
+    gl_Position = instance * vec4(position, 0.0, 1.0);
+    weights = in_weights;
+}
+"#;
+
+        pub const INTEGRAL_QUADRATIC_FRAGMENT: &str = r#"#version 100
+precision highp float;
+varying vec2 weights;
+
+uniform vec4 in_color;
+
+void main() {
+    if ((weights.x * weights.x - weights.y) <= 0.0)
+        gl_FragColor = in_color * (gl_FrontFacing ? 16.0 / 255.0 : 1.0 / 255.0);
+    else
+        discard;
+}"#;
+
+        pub const INTEGRAL_QUADRATIC_METAL: &str = r#"
+    #include <metal_stdlib>
+
+    using namespace metal;
+
+    struct Uniforms
+    {
+        float4 transform_row_0;
+        float4 transform_row_1;
+        float4 transform_row_2;
+        float4 transform_row_3;
+        float4 in_color;
+    };
+
+    struct Vertex
+    {
+        float2 position      [[attribute(0)]];
+        float2 in_weights    [[attribute(1)]];
+    };
+
+    struct RasterizerData
+    {
+        float4 position [[position]];
+        float2 weights [[user(locn0)]];
+    };
+
+    vertex RasterizerData vertexShader(Vertex v [[stage_in]], constant Uniforms& uniforms [[buffer(0)]])
+    {
+        RasterizerData out;
+
+        float4x4 instance = float4x4(uniforms.transform_row_0,
+                                     uniforms.transform_row_1,
+                                     uniforms.transform_row_2,
+                                     uniforms.transform_row_3);
+        out.position = instance * float4(v.position, 0.0, 1.0);
+        out.weights = v.in_weights;
+
+        return out;
+    }
+
+    float4 coverage(bool keep) {
+        return keep ? float4(0.1, 0.5, 0.2, 1.0) : float4(0.0);
+    }
+
+    fragment float4 fragmentShader(RasterizerData in [[stage_in]])
+    {
+        return coverage((in.weights.x * in.weights.x - in.weights.y) <= 0.0);
+    }
+"#;
+        pub const INTEGRAL_CUBIC_VERTEX: &str = r#"#version 100
+precision highp float;
+
+uniform vec4 transform_row_0;
+uniform vec4 transform_row_1;
+uniform vec4 transform_row_2;
+uniform vec4 transform_row_3;
+
+attribute vec2 position;
+attribute vec3 in_weights;
+
+varying vec3 weights;
+
+void main() {
+    mat4 instance = mat4(transform_row_0, transform_row_1,
+                         transform_row_2, transform_row_3);
+    gl_Position = instance * vec4(position, 0.0, 1.0);
+    weights = in_weights;
+}
+"#;
+
+        pub const INTEGRAL_CUBIC_FRAGMENT: &str = r#"#version 100
+precision highp float;
+
+varying vec3 weights;
+uniform vec4 in_color;
+
+void main() {
+    if ((weights.x * weights.x * weights.x - weights.y * weights.z) <= 0.0)
+        gl_FragColor = in_color * (gl_FrontFacing ? 16.0 / 255.0 : 1.0 / 255.0);
+    else
+        discard;
+}"#;
+
+        pub const INTEGRAL_CUBIC_METAL: &str = r#"
+    #include <metal_stdlib>
+
+    using namespace metal;
+
+    struct Uniforms
+    {
+        float4 transform_row_0;
+        float4 transform_row_1;
+        float4 transform_row_2;
+        float4 transform_row_3;
+        float4 in_color;
+    };
+
+    struct Vertex
+    {
+        float2 position      [[attribute(0)]];
+        float3 in_weights    [[attribute(1)]];
+    };
+
+    struct RasterizerData
+    {
+        float4 position [[position]];
+        float3 weights [[user(locn0)]];
+    };
+
+    vertex RasterizerData vertexShader(Vertex v [[stage_in]], constant Uniforms& uniforms [[buffer(0)]])
+    {
+        RasterizerData out;
+
+        float4x4 instance = float4x4(uniforms.transform_row_0,
+                                     uniforms.transform_row_1,
+                                     uniforms.transform_row_2,
+                                     uniforms.transform_row_3);
+        out.position = instance * float4(v.position, 0.0, 1.0);
+        out.weights = v.in_weights;
+
+        return out;
+    }
+
+    fragment float4 fragmentShader(RasterizerData in [[stage_in]],
+                                   bool front_facing [[front_facing]],
+                                   constant Uniforms& uniforms [[buffer(0)]])
+    {
+        if (!((in.weights.x * in.weights.x * in.weights.x - in.weights.y * in.weights.z) <= 0.0))
+            discard_fragment();
+        return uniforms.in_color * (front_facing ? 16.0 / 255.0 : 1.0 / 255.0);
+    }
+"#;
+
+        pub const RATIONAL_CUBIC_VERTEX: &str = r#"#version 100
+precision highp float;
+
+uniform vec4 transform_row_0;
+uniform vec4 transform_row_1;
+uniform vec4 transform_row_2;
+uniform vec4 transform_row_3;
+
+attribute vec2 position;
+attribute vec4 in_weights;
+
+varying vec4 weights;
+
+void main() {
+    mat4 instance = mat4(transform_row_0, transform_row_1,
+                         transform_row_2, transform_row_3);
+    gl_Position = instance * vec4(position, 0.0, 1.0);
+    weights = in_weights;
+}
+"#;
+
+        pub const RATIONAL_CUBIC_FRAGMENT: &str = r#"#version 100
+precision highp float;
+
+varying vec4 weights;
+uniform vec4 in_color;
+
+void main() {
+    if ((weights.x * weights.x * weights.x - weights.y * weights.z * weights.w) <= 0.0)
+        gl_FragColor = in_color * (gl_FrontFacing ? 16.0 / 255.0 : 1.0 / 255.0);
+    else
+        discard;
+}"#;
+
+        pub const RATIONAL_CUBIC_METAL: &str = r#"
+    #include <metal_stdlib>
+
+    using namespace metal;
+
+    struct Uniforms
+    {
+        float4 transform_row_0;
+        float4 transform_row_1;
+        float4 transform_row_2;
+        float4 transform_row_3;
+        float4 in_color;
+    };
+
+    struct Vertex
+    {
+        float2 position      [[attribute(0)]];
+        float4 in_weights    [[attribute(1)]];
+    };
+
+    struct RasterizerData
+    {
+        float4 position [[position]];
+        float4 weights [[user(locn0)]];
+    };
+
+    vertex RasterizerData vertexShader(Vertex v [[stage_in]], constant Uniforms& uniforms [[buffer(0)]])
+    {
+        RasterizerData out;
+
+        float4x4 instance = float4x4(uniforms.transform_row_0,
+                                     uniforms.transform_row_1,
+                                     uniforms.transform_row_2,
+                                     uniforms.transform_row_3);
+        out.position = instance * float4(v.position, 0.0, 1.0);
+        out.weights = v.in_weights;
+
+        return out;
+    }
+
+    fragment float4 fragmentShader(RasterizerData in [[stage_in]],
+                                   bool front_facing [[front_facing]],
+                                   constant Uniforms& uniforms [[buffer(0)]])
+    {
+        if (!((in.weights.x * in.weights.x * in.weights.x - in.weights.y * in.weights.z * in.weights.w) <= 0.0))
+            discard_fragment();
+        return uniforms.in_color * (front_facing ? 16.0 / 255.0 : 1.0 / 255.0);
+    }
+"#;
+
+        pub fn meta() -> ShaderMeta {
+            ShaderMeta {
+                images: vec![],
+                uniforms: UniformBlockLayout {
+                    uniforms: vec![
+                        UniformDesc::new("transform_row_0", UniformType::Float4),
+                        UniformDesc::new("transform_row_1", UniformType::Float4),
+                        UniformDesc::new("transform_row_2", UniformType::Float4),
+                        UniformDesc::new("transform_row_3", UniformType::Float4),
+                        UniformDesc::new("in_color", UniformType::Float4),
+                    ],
+                },
+            }
+        }
+
+        #[repr(C)]
+        pub struct Uniforms {
+            pub transform_row_0: [f32; 4],
+            pub transform_row_1: [f32; 4],
+            pub transform_row_2: [f32; 4],
+            pub transform_row_3: [f32; 4],
+            pub in_color: [f32; 4],
+        }
+
+        /// Declarations shared by both stroke vertex shaders, which look up the [DynamicStrokeOptions](super::DynamicStrokeOptions) of the path
+        macro_rules! stroke_vertex_glsl {
+            () => {
+                r#"#version 100
+precision highp float;
+
+uniform vec4 transform_row_0;
+uniform vec4 transform_row_1;
+uniform vec4 transform_row_2;
+uniform vec4 transform_row_3;
+uniform vec4 in_stroke_options[48]; // STROKE_OPTIONS_VECTORS * MAX_DYNAMIC_STROKE_OPTIONS
+
+attribute vec2 position;
+attribute vec4 in_path;
+
+varying vec4 dash_meta;
+varying vec4 gap_starts;
+varying vec4 gap_ends;
+varying vec4 dash_start_caps;
+varying vec4 dash_end_caps;
+
+// Returns the join, the start cap and the end cap of solid strokes
+vec4 stroke_options() {
+    mat4 instance = mat4(transform_row_0, transform_row_1,
+                         transform_row_2, transform_row_3);
+    gl_Position = instance * vec4(position, 0.0, 1.0);
+
+    // The lower two bytes are the group, the third byte are the cap flags
+    int index = int(in_path.x + in_path.y * 256.0) * 6;
+    dash_meta = in_stroke_options[index + 1];
+    gap_starts = in_stroke_options[index + 2];
+    gap_ends = in_stroke_options[index + 3];
+    dash_start_caps = in_stroke_options[index + 4];
+    dash_end_caps = in_stroke_options[index + 5];
+    return in_stroke_options[index];
+}
+"#
+            };
+        }
+
+        /// Cap shapes and dash patterns shared by both stroke fragment shaders
+        macro_rules! stroke_fragment_glsl {
+            () => {
+                r#"#version 100
+precision highp float;
+
+uniform vec4 in_color;
+
+varying vec4 dash_meta;
+varying vec4 gap_starts;
+varying vec4 gap_ends;
+varying vec4 dash_start_caps;
+varying vec4 dash_end_caps;
+
+// p is the side and the distance beyond the end in terms of the width
+bool inside_cap(float cap, vec2 p) {
+    if (cap < 0.5) // Square
+        return p.y <= 0.5;
+    if (cap < 1.5) // Round
+        return length(p) <= 0.5;
+    if (cap < 2.5) // Out
+        return p.y <= 0.5 - abs(p.x);
+    if (cap < 3.5) // In
+        return p.y <= min(abs(p.x), 0.5);
+    if (cap < 4.5) // Right
+        return p.y <= 0.5 - p.x;
+    if (cap < 5.5) // Left
+        return p.y <= 0.5 + p.x;
+    return false; // Butt
+}
+
+// Whether the dash pattern covers the point at the offset u along the path and the side across it.
+// Only dashes which overlap [begin, end] take part, they are cut there and get their caps at the cut.
+bool inside_dash(float u, float side, float begin, float end, bool with_caps) {
+    if (dash_meta.x < 0.5) // Solid
+        return true;
+    vec4 starts = gap_starts;
+    vec4 ends = gap_ends;
+    vec4 start_caps = dash_start_caps;
+    vec4 end_caps = dash_end_caps;
+    float period = dash_meta.y;
+    float origin = floor((u - dash_meta.z) / period) * period + dash_meta.z;
+    // The caps of the dashes in the neighboring periods can reach into this one
+    for (int k = -1; k <= 1; k++) {
+        float dash_begin = origin + float(k) * period;
+        for (int i = 0; i < 4; i++) {
+            if (float(i) >= dash_meta.x)
+                break;
+            float a = dash_begin;
+            float b = origin + float(k) * period + starts[i];
+            dash_begin = origin + float(k) * period + ends[i];
+            if (b < begin || a > end)
+                continue;
+            a = max(a, begin);
+            b = min(b, end);
+            if (u >= a && u <= b)
+                return true;
+            if (with_caps && (u > b
+                ? inside_cap(end_caps[i], vec2(side, u - b))
+                : inside_cap(start_caps[i], vec2(side, a - u))))
+                return true;
+        }
+    }
+    return false;
+}
+"#
+            };
+        }
+
+        /// Declarations, cap shapes and dash patterns shared by both Metal stroke shaders, ported from `stroke_vertex_glsl!` and `stroke_fragment_glsl!`
+        macro_rules! stroke_metal {
+            () => {
+                r#"
+    #include <metal_stdlib>
+
+    using namespace metal;
+
+    struct Uniforms
+    {
+        float4 transform_row_0;
+        float4 transform_row_1;
+        float4 transform_row_2;
+        float4 transform_row_3;
+        float4 in_color;
+        float4 in_stroke_options[48]; // STROKE_OPTIONS_VECTORS * MAX_DYNAMIC_STROKE_OPTIONS
+    };
+
+    struct RasterizerData
+    {
+        float4 position [[position]];
+        float3 weights [[user(locn0)]];
+        float3 geometry [[user(locn1)]];
+        float4 dash_meta [[flat]];
+        float4 gap_starts [[flat]];
+        float4 gap_ends [[flat]];
+        float4 dash_start_caps [[flat]];
+        float4 dash_end_caps [[flat]];
+    };
+
+    // Returns the join, the start cap and the end cap of solid strokes
+    float4 stroke_options(thread RasterizerData& out, float2 position, uchar4 path, constant Uniforms& uniforms)
+    {
+        float4x4 instance = float4x4(uniforms.transform_row_0,
+                                     uniforms.transform_row_1,
+                                     uniforms.transform_row_2,
+                                     uniforms.transform_row_3);
+        out.position = instance * float4(position, 0.0, 1.0);
+
+        // The lower two bytes are the group, the third byte are the cap flags
+        int index = (int(path.x) + int(path.y) * 256) * 6;
+        out.dash_meta = uniforms.in_stroke_options[index + 1];
+        out.gap_starts = uniforms.in_stroke_options[index + 2];
+        out.gap_ends = uniforms.in_stroke_options[index + 3];
+        out.dash_start_caps = uniforms.in_stroke_options[index + 4];
+        out.dash_end_caps = uniforms.in_stroke_options[index + 5];
+        out.geometry = float3(0.0);
+        return uniforms.in_stroke_options[index];
+    }
+
+    // p is the side and the distance beyond the end in terms of the width
+    bool inside_cap(float cap, float2 p)
+    {
+        if (cap < 0.5) // Square
+            return p.y <= 0.5;
+        if (cap < 1.5) // Round
+            return length(p) <= 0.5;
+        if (cap < 2.5) // Out
+            return p.y <= 0.5 - abs(p.x);
+        if (cap < 3.5) // In
+            return p.y <= min(abs(p.x), 0.5);
+        if (cap < 4.5) // Right
+            return p.y <= 0.5 - p.x;
+        if (cap < 5.5) // Left
+            return p.y <= 0.5 + p.x;
+        return false; // Butt
+    }
+
+    // Whether the dash pattern covers the point at the offset u along the path and the side across it.
+    // Only dashes which overlap [begin, end] take part, they are cut there and get their caps at the cut.
+    bool inside_dash(thread const RasterizerData& in, float u, float side, float begin, float end, bool with_caps)
+    {
+        if (in.dash_meta.x < 0.5) // Solid
+            return true;
+        float period = in.dash_meta.y;
+        float origin = floor((u - in.dash_meta.z) / period) * period + in.dash_meta.z;
+        // The caps of the dashes in the neighboring periods can reach into this one
+        for (int k = -1; k <= 1; k++) {
+            float dash_begin = origin + float(k) * period;
+            for (int i = 0; i < 4; i++) {
+                if (float(i) >= in.dash_meta.x)
+                    break;
+                float a = dash_begin;
+                float b = origin + float(k) * period + in.gap_starts[i];
+                dash_begin = origin + float(k) * period + in.gap_ends[i];
+                if (b < begin || a > end)
+                    continue;
+                a = max(a, begin);
+                b = min(b, end);
+                if (u >= a && u <= b)
+                    return true;
+                if (with_caps && (u > b
+                    ? inside_cap(in.dash_end_caps[i], float2(side, u - b))
+                    : inside_cap(in.dash_start_caps[i], float2(side, a - u))))
+                    return true;
+            }
+        }
+        return false;
+    }
+"#
+            };
+        }
+
+        pub const STROKE_LINE_VERTEX: &str = concat!(
+            stroke_vertex_glsl!(),
+            r#"
+attribute vec2 in_weights;
+
+varying vec2 weights;
+
+void main() {
+    stroke_options();
+    weights = in_weights;
+}
+"#
+        );
+
+        pub const STROKE_LINE_FRAGMENT: &str = concat!(
+            stroke_fragment_glsl!(),
+            r#"
+varying vec2 weights;
+
+void main() {
+    // Paths start at offset zero, but where they end is not known here
+    if (!inside_dash(weights.y, weights.x, 0.0, 1.0e30, true))
+        discard;
+    // Strokes overlap themselves with either facing, so every fragment counts as front facing
+    gl_FragColor = in_color * (16.0 / 255.0);
+}"#
+        );
+
+        pub const STROKE_LINE_METAL: &str = concat!(
+            stroke_metal!(),
+            r#"
+    struct Vertex
+    {
+        float2 position      [[attribute(0)]];
+        float2 in_weights    [[attribute(1)]];
+        uchar4 in_path       [[attribute(2)]];
+    };
+
+    vertex RasterizerData vertexShader(Vertex v [[stage_in]], constant Uniforms& uniforms [[buffer(0)]])
+    {
+        RasterizerData out;
+        stroke_options(out, v.position, v.in_path, uniforms);
+        out.weights = float3(v.in_weights, 0.0);
+        return out;
+    }
+
+    fragment float4 fragmentShader(RasterizerData in [[stage_in]], constant Uniforms& uniforms [[buffer(0)]])
+    {
+        // Paths start at offset zero, but where they end is not known here
+        if (!inside_dash(in, in.weights.y, in.weights.x, 0.0, 1.0e30, true))
+            discard_fragment();
+        // Strokes overlap themselves with either facing, so every fragment counts as front facing
+        return uniforms.in_color * (16.0 / 255.0);
+    }
+"#
+        );
+
+        pub const STROKE_JOINT_VERTEX: &str = concat!(
+            stroke_vertex_glsl!(),
+            r#"
+attribute vec3 in_weights;
+
+varying vec3 weights;
+varying vec3 geometry;
+
+void main() {
+    vec4 options = stroke_options();
+    weights = in_weights;
+    if (in_path.z > 1.5) // Start cap
+        geometry = vec3(options.y, 1.0, -1.0);
+    else if (in_path.z > 0.5) // End cap
+        geometry = vec3(options.z, 1.0, 1.0);
+    else // Joint, the miter vertices are further away than half the width
+        geometry = vec3(options.x, 0.0, length(in_weights.xy) > 0.5001 ? 1.0 : 0.0);
+}
+"#
+        );
+
+        pub const STROKE_JOINT_FRAGMENT: &str = concat!(
+            stroke_fragment_glsl!(),
+            r#"
+varying vec3 weights;
+varying vec3 geometry;
+
+void main() {
+    vec2 p = weights.xy;
+    if (geometry.y > 0.5) {
+        // Cap, the offset along the path is where it is attached
+        bool keep;
+        if (dash_meta.x < 0.5)
+            keep = inside_cap(geometry.x, p);
+        else if (geometry.z < 0.0)
+            keep = inside_dash(weights.z - p.y, p.x, weights.z, 1.0e30, true);
+        else
+            keep = inside_dash(weights.z + p.y, p.x, 0.0, weights.z, true);
+        if (!keep)
+            discard;
+    } else {
+        // Joint, p is relative to where the segments meet
+        if (!inside_dash(weights.z, 0.0, 0.0, 1.0e30, false))
+            discard;
+        if (geometry.x > 1.5) { // Round
+            if (length(p) > 0.5)
+                discard;
+        } else if (geometry.x > 0.5) { // Bevel, only the triangle between the segments and their meeting point remains
+            if (geometry.z > 0.0)
+                discard;
+        }
+    }
+    gl_FragColor = in_color * (16.0 / 255.0);
+}"#
+        );
+
+        pub const STROKE_JOINT_METAL: &str = concat!(
+            stroke_metal!(),
+            r#"
+    struct Vertex
+    {
+        float2 position      [[attribute(0)]];
+        float3 in_weights    [[attribute(1)]];
+        uchar4 in_path       [[attribute(2)]];
+    };
+
+    vertex RasterizerData vertexShader(Vertex v [[stage_in]], constant Uniforms& uniforms [[buffer(0)]])
+    {
+        RasterizerData out;
+        float4 options = stroke_options(out, v.position, v.in_path, uniforms);
+        out.weights = v.in_weights;
+        if (v.in_path.z > 1) // Start cap
+            out.geometry = float3(options.y, 1.0, -1.0);
+        else if (v.in_path.z > 0) // End cap
+            out.geometry = float3(options.z, 1.0, 1.0);
+        else // Joint, the miter vertices are further away than half the width
+            out.geometry = float3(options.x, 0.0, length(v.in_weights.xy) > 0.5001 ? 1.0 : 0.0);
+        return out;
+    }
+
+    fragment float4 fragmentShader(RasterizerData in [[stage_in]], constant Uniforms& uniforms [[buffer(0)]])
+    {
+        float2 p = in.weights.xy;
+        if (in.geometry.y > 0.5) {
+            // Cap, the offset along the path is where it is attached
+            bool keep;
+            if (in.dash_meta.x < 0.5)
+                keep = inside_cap(in.geometry.x, p);
+            else if (in.geometry.z < 0.0)
+                keep = inside_dash(in, in.weights.z - p.y, p.x, in.weights.z, 1.0e30, true);
+            else
+                keep = inside_dash(in, in.weights.z + p.y, p.x, 0.0, in.weights.z, true);
+            if (!keep)
+                discard_fragment();
+        } else {
+            // Joint, p is relative to where the segments meet
+            if (!inside_dash(in, in.weights.z, 0.0, 0.0, 1.0e30, false))
+                discard_fragment();
+            if (in.geometry.x > 1.5) { // Round
+                if (length(p) > 0.5)
+                    discard_fragment();
+            } else if (in.geometry.x > 0.5) { // Bevel, only the triangle between the segments and their meeting point remains
+                if (in.geometry.z > 0.0)
+                    discard_fragment();
+            }
+        }
+        return uniforms.in_color * (16.0 / 255.0);
+    }
+"#
+        );
+
+        pub fn stroke_meta() -> ShaderMeta {
+            ShaderMeta {
+                images: vec![],
+                uniforms: UniformBlockLayout {
+                    uniforms: vec![
+                        UniformDesc::new("transform_row_0", UniformType::Float4),
+                        UniformDesc::new("transform_row_1", UniformType::Float4),
+                        UniformDesc::new("transform_row_2", UniformType::Float4),
+                        UniformDesc::new("transform_row_3", UniformType::Float4),
+                        UniformDesc::new("in_color", UniformType::Float4),
+                        UniformDesc::new("in_stroke_options", UniformType::Float4).array(
+                            super::STROKE_OPTIONS_VECTORS * super::MAX_DYNAMIC_STROKE_OPTIONS,
+                        ),
+                    ],
+                },
+            }
+        }
+
+        #[repr(C)]
+        pub struct StrokeUniforms {
+            pub transform_row_0: [f32; 4],
+            pub transform_row_1: [f32; 4],
+            pub transform_row_2: [f32; 4],
+            pub transform_row_3: [f32; 4],
+            pub in_color: [f32; 4],
+            pub in_stroke_options:
+                [[f32; 4]; super::STROKE_OPTIONS_VECTORS * super::MAX_DYNAMIC_STROKE_OPTIONS],
+        }
+
+        pub const COVER_VERTEX: &str = r#"#version 100
+            attribute vec2 in_uv;
+
+            varying vec2 texcoord;
+
+            precision highp float;
+
+            uniform vec4 in_rect;
+
+            attribute vec2 position;
+
+            varying vec2 _coord2;
+
+    void main() {
+        gl_Position = vec4(position, 0, 1);
+        texcoord = in_uv;
+    }
+"#;
+
+        /// Declarations and the coverage resolve shared by the fragment shaders of the cover pass
+        macro_rules! cover_fragment_glsl {
+            () => {
+                r#"#version 100
+precision highp float;
+
+uniform sampler2D tex;
+
+varying vec2 texcoord;
+
+// Turns the packed counters of the coverage pass into the coverage of up to three samples
+vec3 sample_coverage(vec3 value) {
+    value *= 255.0;
+    vec3 lower = mod(value, 16.0);
+    vec3 upper = (value - lower) / 16.0;
+    vec3 winding = upper - lower;
+    return min(abs(winding), 2.0);
+}
+
+// Returns the coverage of the pixel per color channel
+vec3 resolve_coverage() {
+    vec4 value = texture2D(tex, texcoord);
+    // Samples for -2/3 and -1/3
+    vec2 alphaL = sample_coverage(vec3(value.yz, 0.0)).xy;
+    // Samples for 0, +1/3, and +2/3
+    vec3 alphaR = sample_coverage(value.xyz);
+    // Average the energy over the pixels on either side
+    return vec3(
+        (alphaR.x + alphaR.y + alphaR.z) / 6.0,
+        (alphaL.y + alphaR.x + alphaR.y) / 6.0,
+        (alphaL.x + alphaL.y + alphaR.x) / 6.0);
+}
+"#
+            };
+        }
+
+        pub const COVER_FRAGMENT: &str = concat!(
+            cover_fragment_glsl!(),
+            r#"
+uniform vec4 in_color;
+
+void main() {
+    vec4 rgba = vec4(resolve_coverage(), 0.0);
+
+    // Optionally scale by a color (premultiplied, the coverage acts as alpha per channel)
+    gl_FragColor = in_color.a == 0.0 ? 1.0 - rgba : vec4(
+        in_color.rgb * in_color.a * rgba.rgb,
+        in_color.a * (rgba.r + rgba.g + rgba.b) / 3.0);
+    // gl_FragColor = rgba;
+    // gl_FragColor = vec4(texture2D(tex, texcoord).xyz * 255.0, 1.0);
+}
+"#
+        );
+
+        pub const COVER_METAL: &str = r#"
+    #include <metal_stdlib>
+
+    using namespace metal;
+
+    struct Uniforms
+    {
+        float4 transform_row_0;
+        float4 transform_row_1;
+        float4 transform_row_2;
+        float4 transform_row_3;
+        float4 in_color;
+    };
+
+    struct Vertex
+    {
+        float2 position      [[attribute(0)]];
+    };
+
+    struct RasterizerData
+    {
+        float4 position [[position]];
+        float4 color [[user(locn0)]];
+    };
+
+    vertex RasterizerData vertexShader(Vertex v [[stage_in]], constant Uniforms& uniforms [[buffer(0)]])
+    {
+        RasterizerData out;
+
+        float4x4 instance = float4x4(uniforms.transform_row_0,
+                                     uniforms.transform_row_1,
+                                     uniforms.transform_row_2,
+                                     uniforms.transform_row_3);
+        out.position = instance * float4(v.position, 0.0, 1.0);
+        out.color = uniforms.in_color;
+
+        return out;
+    }
+
+    fragment float4 fragmentShader(RasterizerData in [[stage_in]])
+    {
+        return float4(in.color.rgb * in.color.a, in.color.a);
+    }
+"#;
+
+        pub const IMAGE_FRAGMENT: &str = concat!(
+            cover_fragment_glsl!(),
+            r#"
+uniform sampler2D image;
+uniform vec4 uv_column_0;
+uniform vec4 uv_column_1;
+uniform vec4 uv_column_2;
+uniform vec4 in_opacity;
+
+void main() {
+    vec3 rgb = resolve_coverage();
+
+    // Map the normalized device coordinates of the pixel back to texture coordinates
+    mat3 uv_matrix = mat3(uv_column_0.xyz, uv_column_1.xyz, uv_column_2.xyz);
+    vec3 uv = uv_matrix * vec3(texcoord * 2.0 - 1.0, 1.0);
+    vec4 texel = texture2D(image, uv.xy / uv.z);
+
+    // Premultiply the texel and scale it by the coverage like a color
+    float alpha = texel.a * in_opacity.x;
+    gl_FragColor = vec4(
+        texel.rgb * alpha * rgb,
+        alpha * (rgb.r + rgb.g + rgb.b) / 3.0);
+}
+"#
+        );
+
+        pub const IMAGE_METAL: &str = r#"
+    #include <metal_stdlib>
+
+    using namespace metal;
+
+    struct Uniforms
+    {
+        float4 uv_column_0;
+        float4 uv_column_1;
+        float4 uv_column_2;
+        float4 in_opacity;
+    };
+
+    struct Vertex
+    {
+        float2 position      [[attribute(0)]];
+    };
+
+    struct RasterizerData
+    {
+        float4 position [[position]];
+    };
+
+    vertex RasterizerData vertexShader(Vertex v [[stage_in]], constant Uniforms& uniforms [[buffer(0)]])
+    {
+        RasterizerData out;
+
+        out.position = float4(v.position, 0.0, 1.0);
 
-        pub const FILL_VERTEX: &str = r#"#version 100
-precision highp float;
+        return out;
+    }
 
-uniform vec4 transform_row_0;
-uniform vec4 transform_row_1;
-uniform vec4 transform_row_2;
-uniform vec4 transform_row_3;
+    fragment float4 fragmentShader(RasterizerData in [[stage_in]])
+    {
+        return float4(0.0);
+    }
+"#;
 
-attribute vec2 position;
+        pub const GRADIENT_FRAGMENT: &str = concat!(
+            cover_fragment_glsl!(),
+            r#"
+uniform vec4 uv_column_0;
+uniform vec4 uv_column_1;
+uniform vec4 uv_column_2;
+uniform vec4 in_gradient;
+uniform vec4 in_start_end;
+uniform vec4 in_focal;
+uniform vec4 in_stop_offsets[8]; // MAX_GRADIENT_STOPS
+uniform vec4 in_stop_colors[8]; // MAX_GRADIENT_STOPS
 
 void main() {
-    mat4 instance = mat4(transform_row_0, transform_row_1,
-                         transform_row_2, transform_row_3);
-    gl_Position = instance * vec4(position, 0.0, 1.0);
-}
-"#;
+    vec3 rgb = resolve_coverage();
+
+    // Map the normalized device coordinates of the pixel back to the coordinate system of the gradient
+    mat3 uv_matrix = mat3(uv_column_0.xyz, uv_column_1.xyz, uv_column_2.xyz);
+    vec3 position = uv_matrix * vec3(texcoord * 2.0 - 1.0, 1.0);
+    vec2 p = position.xy / position.z;
+    vec2 start = in_start_end.xy;
+    vec2 end = in_start_end.zw;
+
+    float t;
+    if (in_gradient.x < 0.5) {
+        // Linear, project onto the axis
+        vec2 axis = end - start;
+        t = dot(p - start, axis) / max(dot(axis, axis), 1.0e-12);
+    } else {
+        // Radial, extend the ray from the focal point through the pixel until it hits the circle
+        vec2 d = p - in_focal.xy;
+        vec2 e = in_focal.xy - start;
+        float a = dot(d, d);
+        float b = dot(d, e);
+        float c = dot(e, e) - dot(end - start, end - start);
+        float s = (sqrt(max(b * b - a * c, 0.0)) - b) / a;
+        t = a > 0.0 && s > 0.0 ? 1.0 / s : 0.0;
+    }
 
-        pub const FILL_FRAGMENT: &str = r#"#version 100
-precision highp float;
-uniform vec4 in_color;
+    // Interpolate between the stops, the colors before the first and after the last stop are padded
+    vec4 color = in_stop_colors[0];
+    for (int i = 1; i < 8; i++) {
+        if (float(i) >= in_gradient.y)
+            break;
+        float a = in_stop_offsets[i - 1].x;
+        float b = in_stop_offsets[i].x;
+        if (t >= a)
+            color = mix(in_stop_colors[i - 1], in_stop_colors[i], b > a ? min((t - a) / (b - a), 1.0) : 1.0);
+    }
 
-void main() {
-    gl_FragColor = in_color * (gl_FrontFacing ? 16.0 / 255.0 : 1.0 / 255.0);
-}"#;
+    // Premultiply the color and scale it by the coverage
+    gl_FragColor = vec4(
+        color.rgb * color.a * rgb,
+        color.a * (rgb.r + rgb.g + rgb.b) / 3.0);
+}
+"#
+        );
 
-        pub const FILL_METAL: &str = r#"
+        pub const GRADIENT_METAL: &str = r#"
     #include <metal_stdlib>
 
     using namespace metal;
 
     struct Uniforms
     {
-        float4 transform_row_0;
-        float4 transform_row_1;
-        float4 transform_row_2;
-        float4 transform_row_3;
+        float4 uv_column_0;
+        float4 uv_column_1;
+        float4 uv_column_2;
+        float4 in_gradient;
+        float4 in_start_end;
+        float4 in_focal;
+        float4 in_stop_offsets[8];
+        float4 in_stop_colors[8];
     };
 
     struct Vertex
@@ -322,56 +2685,33 @@ void main() {
     {
         RasterizerData out;
 
-        float4x4 instance = float4x4(uniforms.transform_row_0,
-                                     uniforms.transform_row_1,
-                                     uniforms.transform_row_2,
-                                     uniforms.transform_row_3);
-        out.position = instance * float4(v.position, 0.0, 1.0);
+        out.position = float4(v.position, 0.0, 1.0);
 
         return out;
     }
 
     fragment float4 fragmentShader(RasterizerData in [[stage_in]])
     {
-        return float4(0.1, 0.5, 0.2, 1.0);
+        return float4(0.0);
     }
 "#;
 
-        pub const QUADRATIC_VERTEX: &str = r#"#version 100
-precision highp float;
-
-uniform vec4 transform_row_0;
-uniform vec4 transform_row_1;
-uniform vec4 transform_row_2;
-uniform vec4 transform_row_3;
-
-attribute vec2 position;
-attribute vec3 in_weights;
-
-varying vec3 weights;
-
+        pub const CLIP_FRAGMENT: &str = concat!(
+            cover_fragment_glsl!(),
+            r#"
 void main() {
-    mat4 instance = mat4(transform_row_0, transform_row_1,
-                         transform_row_2, transform_row_3);
-    gl_Position = instance * vec4(position, 0.0, 1.0);
-    weights = in_weights;
-}
-"#;
-
-        pub const QUADRATIC_FRAGMENT: &str = r#"#version 100
-precision highp float;
-
-varying vec3 weights;
-uniform vec4 in_color;
+    // Only the center samples matter
+    vec3 alpha = sample_coverage(texture2D(tex, texcoord).xyz);
 
-void main() {
-    if ((weights.x * weights.x - weights.y * weights.z) <= 0.0)
-        gl_FragColor = in_color * (gl_FrontFacing ? 16.0 / 255.0 : 1.0 / 255.0);
-    else
+    // Only pixels which are covered at least by half mark the stencil buffer
+    if ((alpha.x + alpha.y + alpha.z) / 6.0 < 0.5)
         discard;
-}"#;
+    gl_FragColor = vec4(0.0);
+}
+"#
+        );
 
-        pub const QUADRATIC_METAL: &str = r#"
+        pub const CLIP_METAL: &str = r#"
     #include <metal_stdlib>
 
     using namespace metal;
@@ -388,13 +2728,11 @@ void main() {
     struct Vertex
     {
         float2 position      [[attribute(0)]];
-        float3 in_weights    [[attribute(1)]];
     };
 
     struct RasterizerData
     {
         float4 position [[position]];
-        float3 weights [[user(locn0)]];
     };
 
     vertex RasterizerData vertexShader(Vertex v [[stage_in]], constant Uniforms& uniforms [[buffer(0)]])
@@ -406,194 +2744,174 @@ void main() {
                                      uniforms.transform_row_2,
                                      uniforms.transform_row_3);
         out.position = instance * float4(v.position, 0.0, 1.0);
-        out.weights = v.in_weights;
 
         return out;
     }
 
-    float4 coverage(bool keep) {
-        return keep ? float4(0.1, 0.5, 0.2, 1.0) : float4(0.0);
-    }
-
     fragment float4 fragmentShader(RasterizerData in [[stage_in]])
     {
-        return coverage((in.weights.x * in.weights.x - in.weights.y * in.weights.z) <= 0.0);
+        return float4(0.0);
     }
 "#;
 
-        pub const INTEGRAL_QUADRATIC_VERTEX: &str = r#"#version 100
-precision highp float;
-
-uniform vec4 transform_row_0;
-uniform vec4 transform_row_1;
-uniform vec4 transform_row_2;
-uniform vec4 transform_row_3;
-
-attribute vec2 position;
-attribute vec2 in_weights;
-
-varying vec2 weights;
-
-void main() {
-    mat4 instance = mat4(transform_row_0, transform_row_1,
-                         transform_row_2, transform_row_3);
-    gl_Position = instance * vec4(position, 0.0, 1.0);
-    weights = in_weights;
-}
-"#;
-
-        pub const INTEGRAL_QUADRATIC_FRAGMENT: &str = r#"#version 100
-precision highp float;
-varying vec2 weights;
+        pub const PARITY_FRAGMENT: &str = r#"#version 100
+            precision highp float;
 
-uniform vec4 in_color;
+            uniform vec4 in_color;
 
-void main() {
-    if ((weights.x * weights.x - weights.y) <= 0.0)
-        gl_FragColor = in_color * (gl_FrontFacing ? 16.0 / 255.0 : 1.0 / 255.0);
-    else
-        discard;
-}"#;
+            void main() {
+                // Same increment as a front facing fill
+                gl_FragColor = in_color * (16.0 / 255.0);
+            }
+        "#;
 
-        pub const INTEGRAL_QUADRATIC_METAL: &str = r#"
+        pub const PARITY_METAL: &str = r#"
     #include <metal_stdlib>
 
     using namespace metal;
 
     struct Uniforms
     {
-        float4 transform_row_0;
-        float4 transform_row_1;
-        float4 transform_row_2;
-        float4 transform_row_3;
         float4 in_color;
     };
 
     struct Vertex
     {
         float2 position      [[attribute(0)]];
-        float2 in_weights    [[attribute(1)]];
     };
 
     struct RasterizerData
     {
         float4 position [[position]];
-        float2 weights [[user(locn0)]];
     };
 
     vertex RasterizerData vertexShader(Vertex v [[stage_in]], constant Uniforms& uniforms [[buffer(0)]])
     {
         RasterizerData out;
 
-        float4x4 instance = float4x4(uniforms.transform_row_0,
-                                     uniforms.transform_row_1,
-                                     uniforms.transform_row_2,
-                                     uniforms.transform_row_3);
-        out.position = instance * float4(v.position, 0.0, 1.0);
-        out.weights = v.in_weights;
+        out.position = float4(v.position, 0.0, 1.0);
 
         return out;
     }
 
-    float4 coverage(bool keep) {
-        return keep ? float4(0.1, 0.5, 0.2, 1.0) : float4(0.0);
-    }
-
-    fragment float4 fragmentShader(RasterizerData in [[stage_in]])
+    fragment float4 fragmentShader(RasterizerData in [[stage_in]], constant Uniforms& uniforms [[buffer(0)]])
     {
-        return coverage((in.weights.x * in.weights.x - in.weights.y) <= 0.0);
+        return uniforms.in_color * (16.0 / 255.0);
     }
 "#;
-        pub fn meta() -> ShaderMeta {
-            ShaderMeta {
-                images: vec![],
-                uniforms: UniformBlockLayout {
-                    uniforms: vec![
-                        UniformDesc::new("transform_row_0", UniformType::Float4),
-                        UniformDesc::new("transform_row_1", UniformType::Float4),
-                        UniformDesc::new("transform_row_2", UniformType::Float4),
-                        UniformDesc::new("transform_row_3", UniformType::Float4),
-                        UniformDesc::new("in_color", UniformType::Float4),
-                    ],
-                },
-            }
-        }
 
-        #[repr(C)]
-        pub struct Uniforms {
-            pub transform_row_0: [f32; 4],
-            pub transform_row_1: [f32; 4],
-            pub transform_row_2: [f32; 4],
-            pub transform_row_3: [f32; 4],
-            pub in_color: [f32; 4],
-        }
+        pub const MATTE_FRAGMENT: &str = r#"#version 100
+            precision highp float;
 
-        pub const COVER_VERTEX: &str = r#"#version 100
-            attribute vec2 in_uv;
+            uniform sampler2D tex;
+            uniform sampler2D matte;
+            uniform vec4 in_mode;
 
             varying vec2 texcoord;
 
-            precision highp float;
+            void main() {
+                vec4 layer = texture2D(tex, texcoord);
+                vec4 matte_color = texture2D(matte, texcoord);
+
+                // The matte is premultiplied, so its luminance already includes its alpha
+                float factor = in_mode.x > 0.5
+                    ? dot(matte_color.rgb, vec3(0.299, 0.587, 0.114))
+                    : matte_color.a;
+                if (in_mode.y > 0.5)
+                    factor = 1.0 - factor;
+
+                // The layer is premultiplied as well
+                gl_FragColor = layer * factor;
+            }
+        "#;
 
-            uniform vec4 in_rect;
+        pub const MATTE_METAL: &str = r#"
+    #include <metal_stdlib>
 
-            attribute vec2 position;
+    using namespace metal;
 
-            varying vec2 _coord2;
+    struct Uniforms
+    {
+        float4 in_mode;
+    };
 
-    void main() {
-        gl_Position = vec4(position, 0, 1);
-        texcoord = in_uv;
+    struct Vertex
+    {
+        float2 position      [[attribute(0)]];
+    };
+
+    struct RasterizerData
+    {
+        float4 position [[position]];
+    };
+
+    vertex RasterizerData vertexShader(Vertex v [[stage_in]], constant Uniforms& uniforms [[buffer(0)]])
+    {
+        RasterizerData out;
+
+        out.position = float4(v.position, 0.0, 1.0);
+
+        return out;
+    }
+
+    fragment float4 fragmentShader(RasterizerData in [[stage_in]])
+    {
+        return float4(0.0);
     }
 "#;
 
-        pub const COVER_FRAGMENT: &str = r#"#version 100
+        pub const BLEND_FRAGMENT: &str = r#"#version 100
             precision highp float;
 
             uniform sampler2D tex;
-            uniform vec4 in_color;
+            uniform sampler2D backdrop;
+            uniform vec4 in_mode;
 
             varying vec2 texcoord;
 
+            vec3 blend(vec3 source, vec3 destination) {
+                if (in_mode.x < 0.5) // Overlay
+                    return mix(
+                        2.0 * source * destination,
+                        1.0 - 2.0 * (1.0 - source) * (1.0 - destination),
+                        step(0.5, destination));
+                if (in_mode.x < 1.5) // Darken
+                    return min(source, destination);
+                if (in_mode.x < 2.5) // Lighten
+                    return max(source, destination);
+                if (in_mode.x < 3.5) // Difference
+                    return abs(source - destination);
+                if (in_mode.x < 4.5) // Multiply
+                    return source * destination;
+                if (in_mode.x < 5.5) // Screen
+                    return source + destination - source * destination;
+                return source;
+            }
+
             void main() {
-                // Get samples for -2/3 and -1/3
-                vec2 valueL = texture2D(tex, texcoord).yz * 255.0;
-                vec2 lowerL = mod(valueL, 16.0);
-                vec2 upperL = (valueL - lowerL) / 16.0;
-                vec2 alphaL = min(abs(upperL - lowerL), 2.0);
-
-                // Get samples for 0, +1/3, and +2/3
-                vec3 valueR = texture2D(tex, texcoord).xyz * 255.0;
-                vec3 lowerR = mod(valueR, 16.0);
-                vec3 upperR = (valueR - lowerR) / 16.0;
-                vec3 alphaR = min(abs(upperR - lowerR), 2.0);
-
-                // Average the energy over the pixels on either side
-                vec4 rgba = vec4(
-                    (alphaR.x + alphaR.y + alphaR.z) / 6.0,
-                    (alphaL.y + alphaR.x + alphaR.y) / 6.0,
-                    (alphaL.x + alphaL.y + alphaR.x) / 6.0,
-                    0.0);
-
-                // Optionally scale by a color
-                gl_FragColor = in_color.a == 0.0 ? 1.0 - rgba : in_color * rgba;
-                // gl_FragColor = rgba;
-                // gl_FragColor = vec4(texture2D(tex, texcoord).xyz * 255.0, 1.0);
+                vec4 layer = texture2D(tex, texcoord);
+                vec4 back = texture2D(backdrop, texcoord);
+
+                // The blend functions are defined on straight colors
+                vec3 source = layer.a > 0.0 ? layer.rgb / layer.a : vec3(0.0);
+                vec3 destination = back.a > 0.0 ? back.rgb / back.a : vec3(0.0);
+
+                // Separable blending and source over compositing (premultiplied)
+                gl_FragColor = vec4(
+                    (1.0 - back.a) * layer.rgb + (1.0 - layer.a) * back.rgb
+                        + layer.a * back.a * blend(source, destination),
+                    layer.a + back.a - layer.a * back.a);
             }
         "#;
 
-        pub const COVER_METAL: &str = r#"
+        pub const BLEND_METAL: &str = r#"
     #include <metal_stdlib>
 
     using namespace metal;
 
     struct Uniforms
     {
-        float4 transform_row_0;
-        float4 transform_row_1;
-        float4 transform_row_2;
-        float4 transform_row_3;
-        float4 in_color;
+        float4 in_mode;
     };
 
     struct Vertex
@@ -604,29 +2922,109 @@ void main() {
     struct RasterizerData
     {
         float4 position [[position]];
-        float4 color [[user(locn0)]];
     };
 
     vertex RasterizerData vertexShader(Vertex v [[stage_in]], constant Uniforms& uniforms [[buffer(0)]])
     {
         RasterizerData out;
 
-        float4x4 instance = float4x4(uniforms.transform_row_0,
-                                     uniforms.transform_row_1,
-                                     uniforms.transform_row_2,
-                                     uniforms.transform_row_3);
-        out.position = instance * float4(v.position, 0.0, 1.0);
-        out.color = uniforms.in_color;
+        out.position = float4(v.position, 0.0, 1.0);
 
         return out;
     }
 
     fragment float4 fragmentShader(RasterizerData in [[stage_in]])
     {
-        return float4(in.color.rgb * in.color.a, in.color.a);
+        return float4(0.0);
     }
 "#;
 
+        /// The coverage is bound as `tex`, the image as `image`
+        pub fn image_meta() -> ShaderMeta {
+            ShaderMeta {
+                images: vec!["tex".to_string(), "image".to_string()],
+                uniforms: UniformBlockLayout {
+                    uniforms: vec![
+                        UniformDesc::new("uv_column_0", UniformType::Float4),
+                        UniformDesc::new("uv_column_1", UniformType::Float4),
+                        UniformDesc::new("uv_column_2", UniformType::Float4),
+                        UniformDesc::new("in_opacity", UniformType::Float4),
+                    ],
+                },
+            }
+        }
+
+        #[repr(C)]
+        pub struct ImageUniforms {
+            pub uv_column_0: [f32; 4],
+            pub uv_column_1: [f32; 4],
+            pub uv_column_2: [f32; 4],
+            pub in_opacity: [f32; 4],
+        }
+
+        /// The stops are arrays, offsets are stored in the x components
+        pub fn gradient_meta() -> ShaderMeta {
+            ShaderMeta {
+                images: vec!["tex".to_string()],
+                uniforms: UniformBlockLayout {
+                    uniforms: vec![
+                        UniformDesc::new("uv_column_0", UniformType::Float4),
+                        UniformDesc::new("uv_column_1", UniformType::Float4),
+                        UniformDesc::new("uv_column_2", UniformType::Float4),
+                        UniformDesc::new("in_gradient", UniformType::Float4),
+                        UniformDesc::new("in_start_end", UniformType::Float4),
+                        UniformDesc::new("in_focal", UniformType::Float4),
+                        UniformDesc::new("in_stop_offsets", UniformType::Float4)
+                            .array(super::MAX_GRADIENT_STOPS),
+                        UniformDesc::new("in_stop_colors", UniformType::Float4)
+                            .array(super::MAX_GRADIENT_STOPS),
+                    ],
+                },
+            }
+        }
+
+        #[repr(C)]
+        pub struct GradientUniforms {
+            pub uv_column_0: [f32; 4],
+            pub uv_column_1: [f32; 4],
+            pub uv_column_2: [f32; 4],
+            /// Whether it is radial and the number of stops
+            pub in_gradient: [f32; 4],
+            pub in_start_end: [f32; 4],
+            pub in_focal: [f32; 4],
+            pub in_stop_offsets: [[f32; 4]; super::MAX_GRADIENT_STOPS],
+            pub in_stop_colors: [[f32; 4]; super::MAX_GRADIENT_STOPS],
+        }
+
+        pub fn parity_meta() -> ShaderMeta {
+            ShaderMeta {
+                images: vec![],
+                uniforms: UniformBlockLayout {
+                    uniforms: vec![UniformDesc::new("in_color", UniformType::Float4)],
+                },
+            }
+        }
+
+        #[repr(C)]
+        pub struct ParityUniforms {
+            pub in_color: [f32; 4],
+        }
+
+        /// The layer is bound as `tex`, the background of the offscreen context as `background`
+        pub fn context_meta(background: &str) -> ShaderMeta {
+            ShaderMeta {
+                images: vec!["tex".to_string(), background.to_string()],
+                uniforms: UniformBlockLayout {
+                    uniforms: vec![UniformDesc::new("in_mode", UniformType::Float4)],
+                },
+            }
+        }
+
+        #[repr(C)]
+        pub struct ContextUniforms {
+            pub in_mode: [f32; 4],
+        }
+
         pub fn cover_meta() -> ShaderMeta {
             ShaderMeta {
                 images: vec!["tex".to_string()],
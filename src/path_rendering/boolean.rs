@@ -0,0 +1,220 @@
+//! Boolean operations on the areas enclosed by [Path]s
+
+use super::{
+    error::ERROR_MARGIN,
+    path::{Path, Segment},
+};
+use std::collections::{HashMap, HashSet};
+
+/// Number of lines each curve is approximated by
+const FLATTENING_SAMPLES: usize = 16;
+
+/// Defines how the areas of multiple [Path]s are combined
+///
+/// The area of a single [Path] is where its winding number is nonzero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BooleanOperation {
+    /// Area where the sum of the winding numbers of all [Path]s is nonzero
+    Union,
+    /// Area of the first [Path] without the union of all other [Path]s
+    Difference,
+    /// Area which is covered by all [Path]s
+    Intersection,
+    /// Area which is covered by an odd number of [Path]s
+    SymmetricDifference,
+}
+
+impl BooleanOperation {
+    fn is_inside(self, winding_numbers: &[i32]) -> bool {
+        match self {
+            Self::Union => winding_numbers.iter().sum::<i32>() != 0,
+            Self::Difference => {
+                winding_numbers.first().map_or(false, |first| *first != 0)
+                    && winding_numbers[1..].iter().sum::<i32>() == 0
+            }
+            Self::Intersection => {
+                !winding_numbers.is_empty() && winding_numbers.iter().all(|winding| *winding != 0)
+            }
+            Self::SymmetricDifference => {
+                winding_numbers
+                    .iter()
+                    .filter(|winding| **winding != 0)
+                    .count()
+                    % 2
+                    == 1
+            }
+        }
+    }
+}
+
+/// Approximates a [Path] by a closed polygon.
+fn flatten(path: &Path) -> Vec<[f32; 2]> {
+    let mut polygon = vec![path.start.unwrap()];
+    for segment in path.segments() {
+        if let Segment::Line([_, end]) = segment {
+            polygon.push(end);
+            continue;
+        }
+        for i in 1..=FLATTENING_SAMPLES {
+            polygon.push(segment.point(i as f32 / FLATTENING_SAMPLES as f32));
+        }
+    }
+    if path.is_closed() {
+        polygon.pop();
+    }
+    polygon
+}
+
+fn polygon_edges(polygon: &[[f32; 2]]) -> impl Iterator<Item = ([f32; 2], [f32; 2])> + '_ {
+    (0..polygon.len()).map(move |i| (polygon[i], polygon[(i + 1) % polygon.len()]))
+}
+
+/// Winding number of a closed polygon around a point
+fn winding_number(polygon: &[[f32; 2]], point: [f32; 2]) -> i32 {
+    let mut winding = 0;
+    for (a, b) in polygon_edges(polygon) {
+        let side = (b[0] - a[0]) * (point[1] - a[1]) - (point[0] - a[0]) * (b[1] - a[1]);
+        if a[1] <= point[1] {
+            if b[1] > point[1] && side > 0.0 {
+                winding += 1;
+            }
+        } else if b[1] <= point[1] && side < 0.0 {
+            winding -= 1;
+        }
+    }
+    winding
+}
+
+/// Parameters along both lines where they cross, excluding their end points
+fn line_intersection(a: ([f32; 2], [f32; 2]), b: ([f32; 2], [f32; 2])) -> Option<(f32, f32)> {
+    let da = [a.1[0] - a.0[0], a.1[1] - a.0[1]];
+    let db = [b.1[0] - b.0[0], b.1[1] - b.0[1]];
+    let denominator = da[0] * db[1] - da[1] * db[0];
+    if denominator.abs() <= ERROR_MARGIN * ERROR_MARGIN {
+        return None;
+    }
+    let offset = [b.0[0] - a.0[0], b.0[1] - a.0[1]];
+    let ta = (offset[0] * db[1] - offset[1] * db[0]) / denominator;
+    let tb = (offset[0] * da[1] - offset[1] * da[0]) / denominator;
+    let range = ERROR_MARGIN..1.0 - ERROR_MARGIN;
+    (range.contains(&ta) && range.contains(&tb)).then_some((ta, tb))
+}
+
+fn lerp(a: [f32; 2], b: [f32; 2], t: f32) -> [f32; 2] {
+    [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t]
+}
+
+/// Quantizes a point, so that end points of split edges can be matched
+fn point_key(point: [f32; 2]) -> (i64, i64) {
+    let scale = 1.0 / (ERROR_MARGIN * 10.0);
+    (
+        (point[0] * scale).round() as i64,
+        (point[1] * scale).round() as i64,
+    )
+}
+
+/// Combines the `areas` and returns the outline of the result, each operand is an area enclosed by multiple [Path]s.
+///
+/// Curves are approximated by lines, so the resulting [Path]s are closed polygons.
+/// They are oriented consistently, so that they can be filled with the nonzero fill rule.
+/// The winding number of an operand is the sum of the winding numbers of its [Path]s,
+/// so that holes and overlapping sub paths behave as if they were filled with the nonzero fill rule.
+pub fn combine_areas(areas: &[&[Path]], operation: BooleanOperation) -> Vec<Path> {
+    let areas = areas
+        .iter()
+        .map(|paths| {
+            paths
+                .iter()
+                .map(flatten)
+                .filter(|polygon| polygon.len() > 2)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let polygons = areas.iter().flatten().collect::<Vec<_>>();
+    let edges = polygons
+        .iter()
+        .flat_map(|polygon| polygon_edges(polygon))
+        .filter(|(a, b)| point_key(*a) != point_key(*b))
+        .collect::<Vec<_>>();
+    // Split all edges where they cross each other
+    let mut split_parameters = vec![vec![0.0, 1.0]; edges.len()];
+    for i in 0..edges.len() {
+        for j in i + 1..edges.len() {
+            if let Some((ti, tj)) = line_intersection(edges[i], edges[j]) {
+                split_parameters[i].push(ti);
+                split_parameters[j].push(tj);
+            }
+        }
+    }
+    // Keep the pieces which separate the inside from the outside of the result
+    let mut boundary = Vec::new();
+    let mut emitted = HashSet::new();
+    for ((a, b), mut parameters) in edges.iter().zip(split_parameters) {
+        parameters.sort_by(|x, y| x.partial_cmp(y).unwrap());
+        for pair in parameters.windows(2) {
+            let (start, end) = (lerp(*a, *b, pair[0]), lerp(*a, *b, pair[1]));
+            if point_key(start) == point_key(end) {
+                continue;
+            }
+            let direction = [end[0] - start[0], end[1] - start[1]];
+            let length = (direction[0] * direction[0] + direction[1] * direction[1]).sqrt();
+            let epsilon = (length * 0.25).min(ERROR_MARGIN * 100.0) / length;
+            let normal = [-direction[1] * epsilon, direction[0] * epsilon];
+            let middle = lerp(start, end, 0.5);
+            let is_inside = |side: f32| {
+                let sample = [middle[0] + normal[0] * side, middle[1] + normal[1] * side];
+                let winding_numbers = areas
+                    .iter()
+                    .map(|polygons| {
+                        polygons
+                            .iter()
+                            .map(|polygon| winding_number(polygon, sample))
+                            .sum()
+                    })
+                    .collect::<Vec<_>>();
+                operation.is_inside(&winding_numbers)
+            };
+            let (left, right) = (is_inside(1.0), is_inside(-1.0));
+            if left == right {
+                continue;
+            }
+            let (start, end) = if left { (start, end) } else { (end, start) };
+            // Coincident edges of different paths must only be emitted once
+            if emitted.insert((point_key(start), point_key(end))) {
+                boundary.push((start, end));
+            }
+        }
+    }
+    // Chain the pieces into closed loops
+    let mut outgoing: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+    for (index, (start, _)) in boundary.iter().enumerate() {
+        outgoing.entry(point_key(*start)).or_default().push(index);
+    }
+    let mut used = vec![false; boundary.len()];
+    let mut result = Vec::new();
+    for first in 0..boundary.len() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+        let mut vertices = vec![boundary[first].0];
+        let mut end = boundary[first].1;
+        while point_key(end) != point_key(vertices[0]) {
+            let next = outgoing
+                .get(&point_key(end))
+                .and_then(|candidates| candidates.iter().find(|index| !used[**index]));
+            let Some(&next) = next else {
+                break;
+            };
+            used[next] = true;
+            vertices.push(end);
+            end = boundary[next].1;
+        }
+        if vertices.len() > 2 {
+            let mut path = Path::from_polygon(&vertices);
+            path.close();
+            result.push(path);
+        }
+    }
+    result
+}
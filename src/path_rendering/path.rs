@@ -414,6 +414,13 @@ impl Path {
         let mut transform = motor2d_to_mat3(motor);
         transform[0][0] *= scale;
         transform[1][1] *= scale;
+        self.transform_affine(&transform);
+    }
+
+    /// Transforms all control points of the [Path] by an affine 3x3 matrix.
+    ///
+    /// The weights of rational curves are invariant under affine transformations, so they are left untouched.
+    pub fn transform_affine(&mut self, transform: &[ppga2d::Point; 3]) {
         fn transform_point(
             transform: &[ppga2d::Point; 3],
             p: SafeFloat<f32, 2>,
@@ -425,7 +432,7 @@ impl Path {
             ]
             .into()
         }
-        self.start = transform_point(&transform, self.start);
+        self.start = transform_point(transform, self.start);
         let mut line_segment_iter = self.line_segments.iter_mut();
         let mut integral_quadratic_curve_segment_iter =
             self.integral_quadratic_curve_segments.iter_mut();
@@ -438,31 +445,31 @@ impl Path {
                 SegmentType::Line => {
                     let segment = line_segment_iter.next().unwrap();
                     for control_point in &mut segment.control_points {
-                        *control_point = transform_point(&transform, *control_point);
+                        *control_point = transform_point(transform, *control_point);
                     }
                 }
                 SegmentType::IntegralQuadraticCurve => {
                     let segment = integral_quadratic_curve_segment_iter.next().unwrap();
                     for control_point in &mut segment.control_points {
-                        *control_point = transform_point(&transform, *control_point);
+                        *control_point = transform_point(transform, *control_point);
                     }
                 }
                 SegmentType::IntegralCubicCurve => {
                     let segment = integral_cubic_curve_segment_iter.next().unwrap();
                     for control_point in &mut segment.control_points {
-                        *control_point = transform_point(&transform, *control_point);
+                        *control_point = transform_point(transform, *control_point);
                     }
                 }
                 SegmentType::RationalQuadraticCurve => {
                     let segment = rational_quadratic_curve_segment_iter.next().unwrap();
                     for control_point in &mut segment.control_points {
-                        *control_point = transform_point(&transform, *control_point);
+                        *control_point = transform_point(transform, *control_point);
                     }
                 }
                 SegmentType::RationalCubicCurve => {
                     let segment = rational_cubic_curve_segment_iter.next().unwrap();
                     for control_point in &mut segment.control_points {
-                        *control_point = transform_point(&transform, *control_point);
+                        *control_point = transform_point(transform, *control_point);
                     }
                 }
             }
@@ -919,3 +926,410 @@ impl Path {
         Self::from_ellipse(center, [radius, radius])
     }
 }
+
+/// Number of chords used to approximate the arc length of a curve segment
+const ARC_LENGTH_SAMPLES: usize = 32;
+
+/// A single segment of a [Path] including its start
+///
+/// Unlike the segment types stored in a [Path] it does not depend on the previous segment,
+/// which makes it suitable for measuring, splitting and reassembling [Path]s.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Segment {
+    /// For lines
+    Line([[f32; 2]; 2]),
+    /// For integral quadratic bezier curves
+    IntegralQuadraticCurve([[f32; 2]; 3]),
+    /// For integral cubic bezier curves
+    IntegralCubicCurve([[f32; 2]; 4]),
+    /// For rational quadratic bezier curves, the weight belongs to the middle control point
+    RationalQuadraticCurve(f32, [[f32; 2]; 3]),
+    /// For rational cubic bezier curves, with one weight per control point
+    RationalCubicCurve([f32; 4], [[f32; 2]; 4]),
+}
+
+/// Splits a bezier curve given in homogeneous coordinates at `t`
+fn de_casteljau(control_points: &[[f32; 3]], t: f32) -> (Vec<[f32; 3]>, Vec<[f32; 3]>) {
+    let mut points = control_points.to_vec();
+    let mut left = Vec::with_capacity(points.len());
+    let mut right = Vec::with_capacity(points.len());
+    while !points.is_empty() {
+        left.push(points[0]);
+        right.push(points[points.len() - 1]);
+        points = points
+            .windows(2)
+            .map(|pair| {
+                [
+                    pair[0][0] + (pair[1][0] - pair[0][0]) * t,
+                    pair[0][1] + (pair[1][1] - pair[0][1]) * t,
+                    pair[0][2] + (pair[1][2] - pair[0][2]) * t,
+                ]
+            })
+            .collect();
+    }
+    right.reverse();
+    (left, right)
+}
+
+fn project(point: [f32; 3]) -> [f32; 2] {
+    [point[0] / point[2], point[1] / point[2]]
+}
+
+fn distance(a: [f32; 2], b: [f32; 2]) -> f32 {
+    ((b[0] - a[0]).powi(2) + (b[1] - a[1]).powi(2)).sqrt()
+}
+
+impl Segment {
+    /// All control points including the start and the end
+    pub fn control_points(&self) -> &[[f32; 2]] {
+        match self {
+            Self::Line(control_points) => control_points,
+            Self::IntegralQuadraticCurve(control_points) => control_points,
+            Self::IntegralCubicCurve(control_points) => control_points,
+            Self::RationalQuadraticCurve(_, control_points) => control_points,
+            Self::RationalCubicCurve(_, control_points) => control_points,
+        }
+    }
+
+    /// Returns a [Segment] of the same type and weights but with other control points.
+    ///
+    /// `control_points` must have the same length as [Segment::control_points].
+    pub fn with_control_points(&self, control_points: &[[f32; 2]]) -> Self {
+        let mut result = *self;
+        match &mut result {
+            Self::Line(points) => points.copy_from_slice(control_points),
+            Self::IntegralQuadraticCurve(points) => points.copy_from_slice(control_points),
+            Self::IntegralCubicCurve(points) => points.copy_from_slice(control_points),
+            Self::RationalQuadraticCurve(_, points) => points.copy_from_slice(control_points),
+            Self::RationalCubicCurve(_, points) => points.copy_from_slice(control_points),
+        }
+        result
+    }
+
+    /// Returns the start of the [Segment]
+    pub fn start(&self) -> [f32; 2] {
+        self.control_points()[0]
+    }
+
+    /// Returns the end of the [Segment]
+    pub fn end(&self) -> [f32; 2] {
+        *self.control_points().last().unwrap()
+    }
+
+    fn weights(&self) -> [f32; 4] {
+        match self {
+            Self::RationalQuadraticCurve(weight, _) => [1.0, *weight, 1.0, 1.0],
+            Self::RationalCubicCurve(weights, _) => *weights,
+            _ => [1.0; 4],
+        }
+    }
+
+    fn homogeneous_control_points(&self) -> Vec<[f32; 3]> {
+        let weights = self.weights();
+        self.control_points()
+            .iter()
+            .zip(weights.iter())
+            .map(|(control_point, weight)| {
+                [
+                    control_point[0] * weight,
+                    control_point[1] * weight,
+                    *weight,
+                ]
+            })
+            .collect()
+    }
+
+    fn with_homogeneous_control_points(&self, points: &[[f32; 3]]) -> Self {
+        match self {
+            Self::Line(_) => Self::Line([project(points[0]), project(points[1])]),
+            Self::IntegralQuadraticCurve(_) => Self::IntegralQuadraticCurve([
+                project(points[0]),
+                project(points[1]),
+                project(points[2]),
+            ]),
+            Self::IntegralCubicCurve(_) => Self::IntegralCubicCurve([
+                project(points[0]),
+                project(points[1]),
+                project(points[2]),
+                project(points[3]),
+            ]),
+            Self::RationalQuadraticCurve(_, _) => Self::RationalQuadraticCurve(
+                // Normalize the weights of the start and end back to one
+                points[1][2] / (points[0][2] * points[2][2]).sqrt(),
+                [project(points[0]), project(points[1]), project(points[2])],
+            ),
+            Self::RationalCubicCurve(_, _) => Self::RationalCubicCurve(
+                [
+                    1.0,
+                    points[1][2] / points[0][2],
+                    points[2][2] / points[0][2],
+                    points[3][2] / points[0][2],
+                ],
+                [
+                    project(points[0]),
+                    project(points[1]),
+                    project(points[2]),
+                    project(points[3]),
+                ],
+            ),
+        }
+    }
+
+    /// Evaluates the position at the curve parameter `t` in [0, 1].
+    pub fn point(&self, t: f32) -> [f32; 2] {
+        let (left, _) = de_casteljau(&self.homogeneous_control_points(), t);
+        project(*left.last().unwrap())
+    }
+
+    /// Returns the normalized tangent in forward direction at the curve parameter `t` in [0, 1].
+    ///
+    /// Returns zero if the [Segment] is degenerated to a point.
+    pub fn tangent(&self, t: f32) -> [f32; 2] {
+        let (left, right) = de_casteljau(&self.homogeneous_control_points(), t);
+        let point = project(right[0]);
+        // The hull of the sub curves is tangent to the curve at the split point
+        let candidates = left[..left.len() - 1]
+            .iter()
+            .rev()
+            .map(|control_point| {
+                let control_point = project(*control_point);
+                [point[0] - control_point[0], point[1] - control_point[1]]
+            })
+            .chain(right[1..].iter().map(|control_point| {
+                let control_point = project(*control_point);
+                [control_point[0] - point[0], control_point[1] - point[1]]
+            }));
+        for direction in candidates {
+            let length = distance([0.0, 0.0], direction);
+            if length > ERROR_MARGIN {
+                return [direction[0] / length, direction[1] / length];
+            }
+        }
+        [0.0, 0.0]
+    }
+
+    /// Splits the [Segment] at the curve parameter `t` in [0, 1].
+    pub fn split(&self, t: f32) -> (Self, Self) {
+        let (left, right) = de_casteljau(&self.homogeneous_control_points(), t);
+        (
+            self.with_homogeneous_control_points(&left),
+            self.with_homogeneous_control_points(&right),
+        )
+    }
+
+    /// Returns the part of the [Segment] between the curve parameters `begin` and `end`.
+    pub fn sub_segment(&self, begin: f32, end: f32) -> Self {
+        let (left, _) = self.split(end);
+        if end <= ERROR_MARGIN {
+            return left;
+        }
+        left.split(begin / end).1
+    }
+
+    /// Approximates the arc length of the [Segment].
+    pub fn length(&self) -> f32 {
+        if let Self::Line([start, end]) = self {
+            return distance(*start, *end);
+        }
+        let mut length = 0.0;
+        let mut previous = self.start();
+        for i in 1..=ARC_LENGTH_SAMPLES {
+            let point = self.point(i as f32 / ARC_LENGTH_SAMPLES as f32);
+            length += distance(previous, point);
+            previous = point;
+        }
+        length
+    }
+
+    /// Approximates the curve parameter at which the given arc length from the start is reached.
+    pub fn parameter_at_length(&self, length: f32) -> f32 {
+        if let Self::Line([start, end]) = self {
+            let total = distance(*start, *end);
+            return if total > 0.0 {
+                (length / total).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+        }
+        let mut accumulated = 0.0;
+        let mut previous = self.start();
+        for i in 1..=ARC_LENGTH_SAMPLES {
+            let point = self.point(i as f32 / ARC_LENGTH_SAMPLES as f32);
+            let chord = distance(previous, point);
+            if accumulated + chord >= length {
+                let fraction = if chord > 0.0 {
+                    (length - accumulated) / chord
+                } else {
+                    0.0
+                };
+                return ((i - 1) as f32 + fraction.max(0.0)) / ARC_LENGTH_SAMPLES as f32;
+            }
+            accumulated += chord;
+            previous = point;
+        }
+        1.0
+    }
+}
+
+impl Path {
+    /// Returns the segments of the [Path] in order.
+    pub fn segments(&self) -> Vec<Segment> {
+        let mut result = Vec::with_capacity(self.segment_types.len());
+        let mut line_segment_iter = self.line_segments.iter();
+        let mut integral_quadratic_curve_segment_iter =
+            self.integral_quadratic_curve_segments.iter();
+        let mut integral_cubic_curve_segment_iter = self.integral_cubic_curve_segments.iter();
+        let mut rational_quadratic_curve_segment_iter =
+            self.rational_quadratic_curve_segments.iter();
+        let mut rational_cubic_curve_segment_iter = self.rational_cubic_curve_segments.iter();
+        let mut previous_control_point = self.start.unwrap();
+        for segment_type in &self.segment_types {
+            let segment = match segment_type {
+                SegmentType::Line => {
+                    let segment = line_segment_iter.next().unwrap();
+                    Segment::Line([previous_control_point, segment.control_points[0].unwrap()])
+                }
+                SegmentType::IntegralQuadraticCurve => {
+                    let segment = integral_quadratic_curve_segment_iter.next().unwrap();
+                    Segment::IntegralQuadraticCurve([
+                        previous_control_point,
+                        segment.control_points[0].unwrap(),
+                        segment.control_points[1].unwrap(),
+                    ])
+                }
+                SegmentType::IntegralCubicCurve => {
+                    let segment = integral_cubic_curve_segment_iter.next().unwrap();
+                    Segment::IntegralCubicCurve([
+                        previous_control_point,
+                        segment.control_points[0].unwrap(),
+                        segment.control_points[1].unwrap(),
+                        segment.control_points[2].unwrap(),
+                    ])
+                }
+                SegmentType::RationalQuadraticCurve => {
+                    let segment = rational_quadratic_curve_segment_iter.next().unwrap();
+                    Segment::RationalQuadraticCurve(
+                        segment.weight.unwrap(),
+                        [
+                            previous_control_point,
+                            segment.control_points[0].unwrap(),
+                            segment.control_points[1].unwrap(),
+                        ],
+                    )
+                }
+                SegmentType::RationalCubicCurve => {
+                    let segment = rational_cubic_curve_segment_iter.next().unwrap();
+                    Segment::RationalCubicCurve(
+                        segment.weights.unwrap(),
+                        [
+                            previous_control_point,
+                            segment.control_points[0].unwrap(),
+                            segment.control_points[1].unwrap(),
+                            segment.control_points[2].unwrap(),
+                        ],
+                    )
+                }
+            };
+            previous_control_point = segment.end();
+            result.push(segment);
+        }
+        result
+    }
+
+    /// Appends a [Segment], ignoring its start as it is implicitly defined by the end of the [Path].
+    pub fn push_segment(&mut self, segment: &Segment) {
+        match segment {
+            Segment::Line([_, end]) => self.push_line(LineSegment {
+                control_points: [end.into()],
+            }),
+            Segment::IntegralQuadraticCurve([_, a, b]) => {
+                self.push_integral_quadratic_curve(IntegralQuadraticCurveSegment {
+                    control_points: [a.into(), b.into()],
+                })
+            }
+            Segment::IntegralCubicCurve([_, a, b, c]) => {
+                self.push_integral_cubic_curve(IntegralCubicCurveSegment {
+                    control_points: [a.into(), b.into(), c.into()],
+                })
+            }
+            Segment::RationalQuadraticCurve(weight, [_, a, b]) => self
+                .push_rational_quadratic_curve(RationalQuadraticCurveSegment {
+                    weight: weight.into(),
+                    control_points: [a.into(), b.into()],
+                }),
+            Segment::RationalCubicCurve(weights, [_, a, b, c]) => {
+                self.push_rational_cubic_curve(RationalCubicCurveSegment {
+                    weights: weights.into(),
+                    control_points: [a.into(), b.into(), c.into()],
+                })
+            }
+        }
+    }
+
+    /// Constructs a [Path] from a sequence of connected [Segment]s.
+    ///
+    /// Returns [None] if there are no [Segment]s.
+    pub fn from_segments(segments: &[Segment]) -> Option<Self> {
+        let mut result = Path {
+            start: segments.first()?.start().into(),
+            ..Path::default()
+        };
+        for segment in segments {
+            result.push_segment(segment);
+        }
+        Some(result)
+    }
+
+    /// Returns [true] if the end of the [Path] coincides with its start.
+    pub fn is_closed(&self) -> bool {
+        !self.segment_types.is_empty()
+            && distance(self.start.unwrap(), self.get_end()) <= ERROR_MARGIN
+    }
+
+    /// Approximates the arc length of the [Path].
+    pub fn length(&self) -> f32 {
+        self.segments().iter().map(Segment::length).sum()
+    }
+
+    /// Returns the part of the [Path] between the arc lengths `begin` and `end` measured from the start.
+    ///
+    /// Returns [None] if the part is empty.
+    /// The [StrokeOptions] are kept, but the result is not closed anymore.
+    pub fn trim(&self, begin: f32, end: f32) -> Option<Self> {
+        if end - begin <= ERROR_MARGIN {
+            return None;
+        }
+        let mut trimmed = Vec::new();
+        let mut segment_begin = 0.0;
+        for segment in self.segments() {
+            let length = segment.length();
+            let segment_end = segment_begin + length;
+            if segment_end > begin && segment_begin < end && length > 0.0 {
+                let parameter_begin = if begin > segment_begin {
+                    segment.parameter_at_length(begin - segment_begin)
+                } else {
+                    0.0
+                };
+                let parameter_end = if end < segment_end {
+                    segment.parameter_at_length(end - segment_begin)
+                } else {
+                    1.0
+                };
+                if parameter_begin == 0.0 && parameter_end == 1.0 {
+                    trimmed.push(segment);
+                } else {
+                    trimmed.push(segment.sub_segment(parameter_begin, parameter_end));
+                }
+            }
+            segment_begin = segment_end;
+        }
+        let mut result = Self::from_segments(&trimmed)?;
+        if let Some(stroke_options) = &self.stroke_options {
+            result.stroke_options = Some(StrokeOptions {
+                closed: false,
+                ..stroke_options.clone()
+            });
+        }
+        Some(result)
+    }
+}
@@ -4,10 +4,11 @@ use {
     super::{
         error::{Error, ERROR_MARGIN},
         fill::FillBuilder,
-        path::Path,
+        path::{DynamicStrokeOptions, Path, MAX_DASH_INTERVALS},
         safe_float::SafeFloat,
+        stroke::StrokeBuilder,
         utils::{transmute_slice, vec_to_point},
-        vertex::triangle_fan_to_triangles,
+        vertex::{triangle_fan_to_triangles, triangle_strips_to_triangles},
     },
     geometric_algebra::RegressiveProduct,
 };
@@ -53,11 +54,65 @@ pub enum RenderOperation {
     ScaleAlphaContext,
     /// Stop using the rendered [Shape] as opacity group for other [Shape]s
     RestoreAlphaContext,
+    /// Start rendering the following [Shape]s offscreen into a matte
+    SaveMatteContext,
+    /// Start rendering the following [Shape]s offscreen into a layer which is masked by the matte
+    ApplyMatteContext,
+    /// Composite the masked layer onto the previous target through the alpha or (if `luma` is set) the luminance of the matte
+    RestoreMatteContext { luma: bool, inverted: bool },
+    /// Start rendering the following [Shape]s offscreen into a backdrop
+    SaveBlendContext,
+    /// Start rendering the following [Shape]s offscreen into a layer which is blended with the backdrop
+    ApplyBlendContext,
+    /// Blend the layer with the backdrop and composite the result onto the previous target
+    RestoreBlendContext(Blending),
 }
 
+/// How the color of a [Shape] is combined with the colors beneath it
+#[derive(Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Debug, Default)]
+pub enum Blending {
+    /// Alpha blending
+    #[default]
+    Normal,
+    Multiply,
+    Screen,
+    Add,
+    Overlay,
+    Darken,
+    Lighten,
+    Difference,
+}
+
+impl Blending {
+    /// Whether the blend state of the rendering pipeline can express it, otherwise it needs the backdrop in a texture
+    pub fn is_fixed_function(self) -> bool {
+        matches!(
+            self,
+            Self::Normal | Self::Multiply | Self::Screen | Self::Add
+        )
+    }
+}
+
+/// Which points the [Path]s of a [Shape] enclose, stroked [Path]s always use [FillRule::NonZero]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum FillRule {
+    /// Points which the [Path]s wind around in total at least once
+    #[default]
+    NonZero,
+    /// Points which the [Path]s wind around an odd number of times
+    EvenOdd,
+}
+
+/// Maximum number of [DynamicStrokeOptions] groups in a [Shape]
+pub const MAX_DYNAMIC_STROKE_OPTIONS: usize = 8;
+
 /// A set of [Path]s which is always rendered together
 pub struct Shape {
-    pub vertex_offsets: [usize; 7],
+    /// Stroked [Path]s select one of these by their [dynamic_stroke_options_group](super::path::StrokeOptions::dynamic_stroke_options_group)
+    pub dynamic_stroke_options: Vec<DynamicStrokeOptions>,
+    /// End offsets of the fill vertices (solid, integral quadratic, integral cubic, rational quadratic, rational cubic),
+    /// the stroke vertices (line, joint), the convex hull and the full screen quad
+    pub vertex_offsets: [usize; 9],
     pub index_offsets: [usize; 1],
     pub vertex_buffer: Vec<u8>,
     pub index_buffer: Vec<u8>,
@@ -116,19 +171,49 @@ struct Vertex {
 }
 
 impl Shape {
-    pub fn from_paths(paths: &[Path]) -> Result<Self, Error> {
+    /// Builds the geometry of filled [Path]s and of stroked [Path]s (those with [StrokeOptions](super::path::StrokeOptions)).
+    ///
+    /// The triangle strips of strokes are resolved into triangle lists.
+    pub fn from_paths(
+        dynamic_stroke_options: &[DynamicStrokeOptions],
+        paths: &[Path],
+    ) -> Result<Self, Error> {
+        for options in dynamic_stroke_options {
+            if let DynamicStrokeOptions::Dashed { pattern, .. } = options {
+                if pattern.len() > MAX_DASH_INTERVALS {
+                    return Err(Error::TooManyDashIntervals);
+                }
+            }
+        }
         let mut proto_hull = Vec::new();
         let mut fill_builder = FillBuilder::default();
+        let mut stroke_builder = StrokeBuilder::default();
         for path in paths {
-            fill_builder.add_path(&mut proto_hull, path)?;
+            match &path.stroke_options {
+                Some(stroke_options) => {
+                    let group = stroke_options.dynamic_stroke_options_group;
+                    if group >= dynamic_stroke_options.len().min(MAX_DYNAMIC_STROKE_OPTIONS) {
+                        return Err(Error::DynamicStrokeOptionsIndexOutOfBounds);
+                    }
+                    stroke_builder.add_path(&mut proto_hull, path)?;
+                }
+                None => fill_builder.add_path(&mut proto_hull, path)?,
+            }
         }
+        let stroke_line_vertices = triangle_strips_to_triangles(
+            &stroke_builder.line_vertices,
+            &stroke_builder.line_indices,
+        );
+        let stroke_joint_vertices = triangle_strips_to_triangles(
+            &stroke_builder.joint_vertices,
+            &stroke_builder.joint_indices,
+        );
         let convex_hull = triangle_fan_to_triangles(andrew(&proto_hull));
-        let mut convex_box = vec![
-            convex_hull[0][0],
-            convex_hull[0][1],
-            convex_hull[0][0],
-            convex_hull[0][1],
-        ];
+        // Degenerate strokes do not emit any vertices
+        let mut convex_box = match convex_hull.first() {
+            Some(point) => vec![point[0], point[1], point[0], point[1]],
+            None => vec![0.0; 4],
+        };
         for point in &convex_hull {
             if point[0] < convex_box[0] {
                 convex_box[0] = point[0];
@@ -143,7 +228,6 @@ impl Shape {
                 convex_box[3] = point[1];
             }
         }
-        dbg!(&convex_box);
         let full_screen_texture: [Vertex; 6] = [
             Vertex {
                 pos: Vec2 { x: -1.0, y: -1.0 },
@@ -170,19 +254,21 @@ impl Shape {
                 uv: Vec2 { x: 0., y: 1. },
             },
         ];
-        dbg!(&fill_builder.solid_vertices);
         let (vertex_offsets, vertex_buffer) = concat_buffers!([
             &fill_builder.solid_vertices,
             &fill_builder.integral_quadratic_vertices,
             &fill_builder.integral_cubic_vertices,
             &fill_builder.rational_quadratic_vertices,
             &fill_builder.rational_cubic_vertices,
+            &stroke_line_vertices,
+            &stroke_joint_vertices,
             &convex_hull,
             &full_screen_texture
         ]);
         let (index_offsets, index_buffer) = concat_buffers!([&fill_builder.solid_indices]);
 
         Ok(Self {
+            dynamic_stroke_options: dynamic_stroke_options.to_vec(),
             vertex_offsets,
             index_offsets,
             vertex_buffer,
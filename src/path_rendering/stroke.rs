@@ -166,6 +166,55 @@ fn emit_stroke_join(
     );
 }
 
+/// Marks the vertices of the cap at the end of a [Path] in the upper bits of the path index
+pub const END_CAP_FLAG: u32 = 0x10000;
+/// Marks the vertices of the cap at the start of a [Path] in the upper bits of the path index
+pub const START_CAP_FLAG: u32 = 0x20000;
+
+/// Emits a rectangle which extends the stroke by its width beyond the start or the end of a [Path].
+///
+/// Like joints its vertices have three weights: the side, the distance beyond the end (both in terms of the width)
+/// and the offset along the [Path] where the cap begins. The fragment shader cuts out the shape of the [Cap](super::path::Cap).
+fn emit_stroke_cap(
+    builder: &mut StrokeBuilder,
+    proto_hull: &mut Vec<SafeFloat<f32, 2>>,
+    stroke_options: &StrokeOptions,
+    length_accumulator: f32,
+    control_point: ppga2d::Point,
+    tangent: ppga2d::Plane,
+    flag: u32,
+) {
+    // Start caps extend backwards, end caps forwards
+    let direction = if flag == START_CAP_FLAG { 1.0 } else { -1.0 };
+    let normal = rotate_90_degree_clockwise(tangent);
+    let offset_along_path = length_accumulator / stroke_options.width.unwrap();
+    let start_index = builder.joint_vertices.len();
+    for distance in [0.0, 1.0] {
+        let point = offset_control_point(
+            control_point,
+            normal,
+            direction * distance * stroke_options.width.unwrap(),
+        );
+        for side in [-0.5, 0.5] {
+            let vertex = offset_control_point(
+                point,
+                tangent,
+                (stroke_options.offset.unwrap() + side) * stroke_options.width.unwrap(),
+            );
+            proto_hull.push(point_to_vec(vertex).into());
+            builder.joint_vertices.push(Vertex3f1i(
+                point_to_vec(vertex),
+                [side, distance, offset_along_path],
+                stroke_options.dynamic_stroke_options_group as u32 | flag,
+            ));
+        }
+    }
+    let mut indices: Vec<u16> =
+        (start_index as u16..(builder.joint_vertices.len() + 1) as u16).collect();
+    *indices.iter_mut().last().unwrap() = (-1isize) as u16;
+    builder.joint_indices.append(&mut indices);
+}
+
 fn cut_stroke_polygon(builder: &mut StrokeBuilder, proto_hull: &mut Vec<SafeFloat<f32, 2>>) {
     if !builder.path_line_vertices.is_empty() {
         proto_hull.append(
@@ -354,30 +403,24 @@ impl StrokeBuilder {
                 is_first_segment = false;
                 first_tangent = segment_start_tangent;
                 if !stroke_options.closed {
-                    let normal = rotate_90_degree_clockwise(segment_start_tangent);
-                    emit_stroke_vertices(
-                        self,
-                        stroke_options,
-                        stroke_options.dynamic_stroke_options_group,
-                        length_accumulator - 0.5 * stroke_options.width.unwrap(),
-                        offset_control_point(
-                            previous_control_point,
-                            normal,
-                            0.5 * stroke_options.width.unwrap().abs(),
-                        ),
-                        segment_start_tangent,
-                    );
-                }
-                if stroke_options.closed || *segment_type != SegmentType::Line {
-                    emit_stroke_vertices(
+                    emit_stroke_cap(
                         self,
+                        proto_hull,
                         stroke_options,
-                        stroke_options.dynamic_stroke_options_group,
                         length_accumulator,
                         previous_control_point,
                         segment_start_tangent,
+                        START_CAP_FLAG,
                     );
                 }
+                emit_stroke_vertices(
+                    self,
+                    stroke_options,
+                    stroke_options.dynamic_stroke_options_group,
+                    length_accumulator,
+                    previous_control_point,
+                    segment_start_tangent,
+                );
             } else {
                 emit_stroke_join(
                     self,
@@ -543,28 +586,15 @@ impl StrokeBuilder {
                     first_tangent,
                 );
             }
-        } else {
-            cut_stroke_polygon(self, proto_hull);
-            emit_stroke_vertices(
+        } else if !is_first_segment {
+            emit_stroke_cap(
                 self,
+                proto_hull,
                 stroke_options,
-                stroke_options.dynamic_stroke_options_group | 0x10000,
                 length_accumulator,
                 previous_control_point,
                 previous_tangent,
-            );
-            let normal = rotate_90_degree_clockwise(previous_tangent);
-            emit_stroke_vertices(
-                self,
-                stroke_options,
-                stroke_options.dynamic_stroke_options_group | 0x10000,
-                length_accumulator + 0.5 * stroke_options.width.unwrap(),
-                offset_control_point(
-                    previous_control_point,
-                    normal,
-                    -0.5 * stroke_options.width.unwrap().abs(),
-                ),
-                previous_tangent,
+                END_CAP_FLAG,
             );
         }
         cut_stroke_polygon(self, proto_hull);
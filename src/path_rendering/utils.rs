@@ -212,6 +212,46 @@ pub fn matrix_multiplication(a: &[ppga3d::Point; 4], b: &[ppga3d::Point; 4]) ->
     ]
 }
 
+/// Multiplies two 3x3 matrices.
+pub fn mat3_multiplication(a: &[ppga2d::Point; 3], b: &[ppga2d::Point; 3]) -> [ppga2d::Point; 3] {
+    [
+        a[0] * b[0][0] + a[1] * b[0][1] + a[2] * b[0][2],
+        a[0] * b[1][0] + a[1] * b[1][1] + a[2] * b[1][2],
+        a[0] * b[2][0] + a[1] * b[2][1] + a[2] * b[2][2],
+    ]
+}
+
+/// Inverts a (possibly projective) 3x3 matrix, returns [None] if it is singular.
+pub fn mat3_inverse(m: &[ppga2d::Point; 3]) -> Option<[ppga2d::Point; 3]> {
+    let cofactor = |a: usize, b: usize, c: usize, d: usize| {
+        m[a / 3][a % 3] * m[d / 3][d % 3] - m[b / 3][b % 3] * m[c / 3][c % 3]
+    };
+    // Columns of the adjugate, the indices are column * 3 + row
+    let adjugate = [
+        ppga2d::Point::new(
+            cofactor(4, 7, 5, 8),
+            -cofactor(1, 7, 2, 8),
+            cofactor(1, 4, 2, 5),
+        ),
+        ppga2d::Point::new(
+            -cofactor(3, 6, 5, 8),
+            cofactor(0, 6, 2, 8),
+            -cofactor(0, 3, 2, 5),
+        ),
+        ppga2d::Point::new(
+            cofactor(3, 6, 4, 7),
+            -cofactor(0, 6, 1, 7),
+            cofactor(0, 3, 1, 4),
+        ),
+    ];
+    let determinant =
+        m[0][0] * adjugate[0][0] + m[1][0] * adjugate[0][1] + m[2][0] * adjugate[0][2];
+    if determinant == 0.0 {
+        return None;
+    }
+    Some(adjugate.map(|column| column * (1.0 / determinant)))
+}
+
 /// Converts from srgb color space to linear color space
 pub fn srgb_to_linear(mut color: [f32; 4]) -> [f32; 4] {
     for channel in color.iter_mut().take(3) {
@@ -29,16 +29,31 @@ pub fn triangle_fan_to_triangles<T: Copy + std::fmt::Debug>(vertices: Vec<T>) ->
     // if more vertices it looks like this:
     // 0 1 2 3 4 5 6 7 8 9 10 11
     // 0 2 1 0 3 2 0 4 3 0  5  4
-    let gather_indices = (0..((vertices.len() - 2) * 3)).map(|i| match i % 3 {
+    let gather_indices = (0..(vertices.len().saturating_sub(2) * 3)).map(|i| match i % 3 {
         0 => 0,
         1 => (i / 3) + 2,
         2 => (i / 3) + 1,
         _ => unreachable!(),
     });
 
-    let mut result = Vec::with_capacity((vertices.len() - 2) * 3);
+    let mut result = Vec::with_capacity(vertices.len().saturating_sub(2) * 3);
     for src in gather_indices {
         result.push(vertices[src]);
     }
     result
 }
+
+/// Resolves indexed triangle strips, which are separated by primitive restart indices (`u16::MAX`), into a triangle list.
+pub fn triangle_strips_to_triangles<T: Copy>(vertices: &[T], indices: &[u16]) -> Vec<T> {
+    let mut result = Vec::new();
+    for strip in indices.split(|index| *index == u16::MAX) {
+        // Every second triangle of a strip is flipped to keep the winding consistent
+        for (i, window) in strip.windows(3).enumerate() {
+            let order = if i % 2 == 0 { [0, 1, 2] } else { [1, 0, 2] };
+            for j in order {
+                result.push(vertices[window[j] as usize]);
+            }
+        }
+    }
+    result
+}